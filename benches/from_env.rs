@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 #[derive(serde::Deserialize)]
 #[allow(dead_code)]
@@ -16,5 +18,88 @@ fn from_env_bench(c: &mut Criterion) {
         },
     );
 }
-criterion_group!(bench_from_env, from_env_bench);
+
+#[derive(serde::Deserialize)]
+#[allow(dead_code)]
+struct WideStruct {
+    f0: String,
+    f1: String,
+    f2: String,
+    f3: String,
+    f4: String,
+    f5: String,
+    f6: String,
+    f7: String,
+    f8: String,
+    f9: String,
+    f10: String,
+    f11: String,
+    f12: String,
+    f13: String,
+    f14: String,
+    f15: String,
+    f16: String,
+    f17: String,
+    f18: String,
+    f19: String,
+    f20: String,
+    f21: String,
+    f22: String,
+    f23: String,
+    f24: String,
+    f25: String,
+    f26: String,
+    f27: String,
+    f28: String,
+    f29: String,
+    f30: String,
+    f31: String,
+    f32: String,
+    f33: String,
+    f34: String,
+    f35: String,
+    f36: String,
+    f37: String,
+    f38: String,
+    f39: String,
+    f40: String,
+    f41: String,
+    f42: String,
+    f43: String,
+    f44: String,
+    f45: String,
+    f46: String,
+    f47: String,
+    f48: String,
+    f49: String,
+}
+
+fn from_iter_wide_bench(c: &mut Criterion) {
+    let vars: Vec<_> = (0..50).map(|i| (format!("F{i}"), "x".repeat(64))).collect();
+
+    c.bench_function("from_iter_wide", |b| {
+        b.iter(|| {
+            black_box(serde_env::from_iter::<_, _, WideStruct>(
+                vars.iter().cloned(),
+            ))
+        })
+    });
+}
+
+fn from_node_map_bench(c: &mut Criterion) {
+    let node: serde_env::Node = (0..50)
+        .map(|i| (format!("APP_{}_KEY_{i}", i % 5), i.to_string()))
+        .collect();
+
+    c.bench_function("from_node_map", |b| {
+        b.iter(|| black_box(serde_env::from_node::<HashMap<String, String>>(&node)))
+    });
+}
+
+criterion_group!(
+    bench_from_env,
+    from_env_bench,
+    from_iter_wide_bench,
+    from_node_map_bench
+);
 criterion_main!(bench_from_env);
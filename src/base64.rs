@@ -0,0 +1,74 @@
+//! Base64 encoded bytes like `KEY=aGVsbG8=`.
+//!
+//! Enabled by the `base64` feature. Values are decoded with the standard
+//! alphabet and padding.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::base64::deserialize")]
+//!     key: Vec<u8>,
+//! }
+//!
+//! let t: Test = from_iter([("KEY", "aGVsbG8=")]).expect("deserialize from iter");
+//! assert_eq!(t.key, b"hello");
+//! ```
+
+use ::base64::engine::general_purpose::STANDARD;
+use ::base64::Engine;
+use serde::Deserializer;
+
+use crate::error::{parse_str, Error};
+
+/// Decode a base64 string into bytes.
+pub fn parse(s: &str) -> Result<Vec<u8>, Error> {
+    STANDARD.decode(s.trim()).map_err(Error::new)
+}
+
+/// Deserialize a base64 string into bytes.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::base64::deserialize")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_str(d, parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::error::ErrorKind;
+    use crate::from_iter;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(deserialize_with = "super::deserialize")]
+        key: Vec<u8>,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let t: Test = from_iter([("KEY", "aGVsbG8=")]).expect("must success");
+        assert_eq!(t.key, b"hello");
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let err = from_iter::<_, _, Test>([("KEY", "not base64!")]).expect_err("must fail");
+        assert!(err.to_string().contains("Invalid"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("key"));
+                assert!(source.downcast_ref::<::base64::DecodeError>().is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+    }
+}
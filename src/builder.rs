@@ -0,0 +1,1060 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::Path;
+
+use serde::de;
+
+use crate::de::{env_key, Deserializer};
+use crate::dotenv;
+use crate::error::{Error, MissingField};
+use crate::report::{ConfigReport, ReportEntry, Source};
+use crate::value::{env_vars_filtered, is_uppercase, Node};
+
+/// Options that control how env values are interpreted.
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    pub(crate) trim_seq_elements: bool,
+    pub(crate) empty_string_is_none: bool,
+    pub(crate) redact: Option<fn(&str) -> bool>,
+    pub(crate) prefix_separator: String,
+    pub(crate) detect_collisions: bool,
+    pub(crate) infer_float: bool,
+    pub(crate) infer_int128: bool,
+    pub(crate) infer_bool: bool,
+    pub(crate) list_delimiter: char,
+    pub(crate) group_delimiter: Option<char>,
+    pub(crate) read_file_keys: bool,
+    pub(crate) seq_inference: SeqInference,
+    pub(crate) byte_encoding: ByteEncoding,
+    pub(crate) enum_case_insensitive: bool,
+    #[cfg(feature = "json")]
+    pub(crate) json_seq: bool,
+    pub(crate) uppercase_wins: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            trim_seq_elements: true,
+            empty_string_is_none: true,
+            redact: None,
+            prefix_separator: "_".to_string(),
+            detect_collisions: false,
+            infer_float: false,
+            infer_int128: false,
+            infer_bool: true,
+            list_delimiter: ',',
+            group_delimiter: None,
+            read_file_keys: false,
+            seq_inference: SeqInference::Always,
+            byte_encoding: ByteEncoding::Raw,
+            enum_case_insensitive: false,
+            #[cfg(feature = "json")]
+            json_seq: false,
+            uppercase_wins: true,
+        }
+    }
+}
+
+/// When untyped targets see a delimited value as a sequence.
+///
+/// Used by [`EnvDeserializer::seq_inference`]. Typed sequence fields like
+/// `Vec<String>` always split values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeqInference {
+    /// Any value containing the list delimiter is a sequence, like `a,`.
+    #[default]
+    Always,
+    /// Values are never sequences.
+    Never,
+    /// Only values with at least two list delimiters are sequences, so
+    /// `1,2,3` is a sequence while `Hello, test` is a string.
+    OnlyWhenMultipleCommas,
+}
+
+impl SeqInference {
+    /// Check if the value should be seen as a sequence.
+    pub(crate) fn infer(&self, v: &str, opts: &Options) -> bool {
+        let delimited =
+            v.contains(opts.list_delimiter) || opts.group_delimiter.is_some_and(|d| v.contains(d));
+        match self {
+            SeqInference::Always => delimited,
+            SeqInference::Never => false,
+            SeqInference::OnlyWhenMultipleCommas => {
+                v.matches(opts.list_delimiter).count() >= 2
+                    || opts.group_delimiter.is_some_and(|d| v.contains(d))
+            }
+        }
+    }
+}
+
+/// How bytes fields read their values.
+///
+/// Used by [`EnvDeserializer::byte_encoding`]. It only affects fields that
+/// deserialize bytes, like `serde_bytes::ByteBuf` or
+/// `#[serde(with = "serde_bytes")]`, while `Vec<u8>` is still a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ByteEncoding {
+    /// Bytes of the value as is, so `KEY=hello` is `b"hello"`.
+    #[default]
+    Raw,
+    /// Hex encoded value like `KEY=68656c6c6f`, enabled by the `hex` feature.
+    #[cfg(feature = "hex")]
+    Hex,
+    /// Base64 encoded value like `KEY=aGVsbG8=`, enabled by the `base64`
+    /// feature.
+    #[cfg(feature = "base64")]
+    Base64,
+}
+
+impl ByteEncoding {
+    /// Decode the value, or `None` if bytes are read as is.
+    #[cfg_attr(not(any(feature = "hex", feature = "base64")), allow(unused_variables))]
+    pub(crate) fn decode(&self, v: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self {
+            ByteEncoding::Raw => Ok(None),
+            #[cfg(feature = "hex")]
+            ByteEncoding::Hex => crate::hex::parse(v).map(Some),
+            #[cfg(feature = "base64")]
+            ByteEncoding::Base64 => crate::base64::parse(v).map(Some),
+        }
+    }
+}
+
+/// Context shared by all deserializers during one deserialization.
+#[derive(Clone, Copy)]
+pub(crate) struct Context<'a> {
+    pub(crate) opts: &'a Options,
+    report: Option<&'a RefCell<ConfigReport>>,
+    /// Full keys of missing fields found by earlier passes of
+    /// [`try_from_env`](EnvDeserializer::try_from_env), filled with
+    /// placeholders so that serde reports the next one.
+    missing: Option<&'a HashSet<String>>,
+    /// Whether values are buffered by serde for `#[serde(flatten)]`, which
+    /// sees decimals like `1.5` as floats, so that a flattened
+    /// `HashMap<String, f64>` works.
+    pub(crate) flatten: bool,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(opts: &'a Options) -> Self {
+        Self {
+            opts,
+            report: None,
+            missing: None,
+            flatten: false,
+        }
+    }
+
+    /// Context for values buffered by serde for `#[serde(flatten)]`.
+    pub(crate) fn flattened(self) -> Self {
+        Self {
+            flatten: true,
+            ..self
+        }
+    }
+
+    fn with_report(opts: &'a Options, report: &'a RefCell<ConfigReport>) -> Self {
+        Self {
+            report: Some(report),
+            ..Self::new(opts)
+        }
+    }
+
+    fn with_missing(opts: &'a Options, missing: &'a HashSet<String>) -> Self {
+        Self {
+            missing: Some(missing),
+            ..Self::new(opts)
+        }
+    }
+
+    /// Whether full keys are tracked.
+    fn tracking(&self) -> bool {
+        self.report.is_some() || self.missing.is_some()
+    }
+
+    /// Join the full key of a child, only tracked while reporting or
+    /// collecting missing fields.
+    pub(crate) fn join(&self, path: &str, key: &str) -> String {
+        if !self.tracking() {
+            String::new()
+        } else if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}_{key}")
+        }
+    }
+
+    /// Set the full key of missing field error.
+    pub(crate) fn locate(&self, err: Error, path: &str) -> Error {
+        if !self.tracking() {
+            return err;
+        }
+        err.with_missing_key(|field| self.join(path, &env_key(field)))
+    }
+
+    /// Whether the field is known to be missing.
+    pub(crate) fn is_missing(&self, path: &str) -> bool {
+        self.missing.is_some_and(|v| v.contains(path))
+    }
+
+    /// Whether this pass only looks for more missing fields, so values
+    /// are never used.
+    pub(crate) fn finding_missing(&self) -> bool {
+        self.missing.is_some_and(|v| !v.is_empty())
+    }
+
+    pub(crate) fn record_env(&self, path: &str, value: &str) {
+        if let Some(report) = self.report {
+            let key = path.to_uppercase();
+            let redacted = self.opts.redact.is_some_and(|f| f(&key));
+            report.borrow_mut().entries.push(ReportEntry {
+                key,
+                source: Source::Env,
+                value: (!redacted).then(|| value.to_string()),
+                redacted,
+            })
+        }
+    }
+
+    pub(crate) fn record_default(&self, path: &str) {
+        if let Some(report) = self.report {
+            report.borrow_mut().entries.push(ReportEntry {
+                key: path.to_uppercase(),
+                source: Source::Default,
+                value: None,
+                redacted: false,
+            })
+        }
+    }
+}
+
+/// Builder to deserialize env into structs with custom options.
+///
+/// The free functions like [`from_env`](crate::from_env) are shortcuts of
+/// this builder with default options.
+///
+/// Options are set by chainable setters, and deserializing is done by
+/// terminal methods like [`from_env`](EnvDeserializer::from_env),
+/// [`from_prefix`](EnvDeserializer::from_prefix) and
+/// [`from_iter`](EnvDeserializer::from_iter). A builder can be reused for
+/// several deserializations.
+///
+/// | Option | Default |
+/// |---|---|
+/// | [`trim_seq_elements`](EnvDeserializer::trim_seq_elements) | `true` |
+/// | [`empty_string_is_none`](EnvDeserializer::empty_string_is_none) | `true` |
+/// | [`prefix_separator`](EnvDeserializer::prefix_separator) | `"_"` |
+/// | [`detect_collisions`](EnvDeserializer::detect_collisions) | `false` |
+/// | [`infer_float`](EnvDeserializer::infer_float) | `false` |
+/// | [`infer_int128`](EnvDeserializer::infer_int128) | `false` |
+/// | [`infer_bool`](EnvDeserializer::infer_bool) | `true` |
+/// | [`list_delimiter`](EnvDeserializer::list_delimiter) | `,` |
+/// | [`group_delimiter`](EnvDeserializer::group_delimiter) | none |
+/// | [`seq_inference`](EnvDeserializer::seq_inference) | [`SeqInference::Always`] |
+/// | [`byte_encoding`](EnvDeserializer::byte_encoding) | [`ByteEncoding::Raw`] |
+/// | [`enum_case_insensitive`](EnvDeserializer::enum_case_insensitive) | `false` |
+/// | `json_seq` (with the `json` feature) | `false` |
+/// | [`read_file_keys`](EnvDeserializer::read_file_keys) | `false` |
+/// | [`uppercase_wins`](EnvDeserializer::uppercase_wins) | `true` |
+/// | [`redact`](EnvDeserializer::redact) | none |
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::EnvDeserializer;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Test {
+///     tags: Vec<String>,
+/// }
+///
+/// let t: Test = EnvDeserializer::new()
+///     .trim_seq_elements(false)
+///     .from_iter([("TAGS", "a, b")])
+///     .expect("deserialize from iter");
+///
+/// assert_eq!(t.tags, vec!["a".to_string(), " b".to_string()]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvDeserializer {
+    opts: Options,
+}
+
+#[allow(clippy::wrong_self_convention)]
+impl EnvDeserializer {
+    /// Create a new builder with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trim whitespace around every sequence element or not.
+    ///
+    /// Enabled by default, so `TAGS= a , b ` is `["a", "b"]`. Disable it
+    /// to keep meaningful whitespace: `[" a ", " b "]`.
+    ///
+    /// It only matters for string elements, since numbers and booleans like
+    /// `1, 2` are always trimmed.
+    pub fn trim_seq_elements(mut self, trim: bool) -> Self {
+        self.opts.trim_seq_elements = trim;
+        self
+    }
+
+    /// Treat an empty variable as unset or not.
+    ///
+    /// Enabled by default, so `PROVIDED=` is `None` for `Option<String>` and
+    /// is the same as an unset `PROVIDED`. Disable it to get
+    /// `Some(String::new())` instead, while an unset variable is still `None`.
+    ///
+    /// The same goes for sequences, so disable it to tell an empty
+    /// `Option<Vec<u32>>` from an unset one: it's then `None` for an unset
+    /// `FOO`, `Some(vec![])` for `FOO=` and `Some(vec![1, 2])` for
+    /// `FOO=1,2`. Enabled, `FOO=` is `None` like an unset `FOO`, since
+    /// `Option` is decided before the inner type is known.
+    ///
+    /// Map entries follow it too: enabled, `A=` is not in
+    /// `HashMap<String, Option<String>>` at all, like an unset `A`. Disabled,
+    /// it's `Some("")`.
+    pub fn empty_string_is_none(mut self, enable: bool) -> Self {
+        self.opts.empty_string_is_none = enable;
+        self
+    }
+
+    /// Set the separator between prefix and the rest of key.
+    ///
+    /// Defaults to `_`, so prefix `APP` matches `APP_HOME`. Set it to `__`
+    /// to match `APP__HOME` instead. It only affects the prefix boundary,
+    /// nested fields are still separated by `_`.
+    ///
+    /// Set it to an empty string to strip the prefix with no separator, so
+    /// prefix `APP` matches `APPHOME` as `home`.
+    pub fn prefix_separator(mut self, sep: impl Into<String>) -> Self {
+        self.opts.prefix_separator = sep.into();
+        self
+    }
+
+    /// Fail on keys that are both a value and a prefix of nested keys.
+    ///
+    /// Disabled by default, so with `PUB=a` and `PUB_HOSTED_URL=b`, a scalar
+    /// field `pub` silently takes `a` and a struct field `pub` silently
+    /// takes `hosted.url`. Enable it to return an error instead.
+    pub fn detect_collisions(mut self, enable: bool) -> Self {
+        self.opts.detect_collisions = enable;
+        self
+    }
+
+    /// Infer floats for untyped targets or not.
+    ///
+    /// Disabled by default, so targets that don't know their type like
+    /// `serde_json::Value` or untagged enums see `1.5` and `inf` as strings.
+    /// Enable it to see them as `f64`, including `inf`, `-inf` and `nan`.
+    ///
+    /// Typed `f64` fields always accept these values, and so do values of
+    /// a `#[serde(flatten)]` map like `HashMap<String, f64>`, which see
+    /// decimals like `1.5` as floats anyway.
+    pub fn infer_float(mut self, enable: bool) -> Self {
+        self.opts.infer_float = enable;
+        self
+    }
+
+    /// Infer 128-bit integers for untyped targets or not.
+    ///
+    /// Untyped targets see integers as `u64` or `i64`. Integers beyond that
+    /// are strings by default, since serde can't buffer 128-bit integers for
+    /// untagged enums and flatten. Enable it to see them as `u128` or `i128`
+    /// first, for hand-written visitors with `visit_u128` and `visit_i128`.
+    /// `serde_json::Value` still rejects them, and values under
+    /// `#[serde(flatten)]` stay strings.
+    ///
+    /// The full order is `u64`/`i64`, `u128`/`i128` if enabled, `f64` if
+    /// [`infer_float`](EnvDeserializer::infer_float) is enabled, and string.
+    ///
+    /// Typed `u128` and `i128` fields are not affected.
+    pub fn infer_int128(mut self, enable: bool) -> Self {
+        self.opts.infer_int128 = enable;
+        self
+    }
+
+    /// Infer booleans for untyped targets or not.
+    ///
+    /// Enabled by default, so targets that don't know their type like
+    /// `serde_json::Value` see `true` and `False` as booleans. Disable it to
+    /// see them as strings.
+    ///
+    /// Typed `bool` fields are not affected.
+    pub fn infer_bool(mut self, enable: bool) -> Self {
+        self.opts.infer_bool = enable;
+        self
+    }
+
+    /// Set the delimiter between sequence elements.
+    ///
+    /// Defaults to `,`, so `TAGS=a,b` is `["a", "b"]`.
+    pub fn list_delimiter(mut self, delimiter: char) -> Self {
+        self.opts.list_delimiter = delimiter;
+        self
+    }
+
+    /// Set the delimiter between groups of nested sequences.
+    ///
+    /// Unset by default. Set it to `;` to read `MATRIX=1,2;3,4` as
+    /// `[[1, 2], [3, 4]]` for `Vec<Vec<u32>>`. Empty groups are kept, so
+    /// `1,2;;3` is `[[1, 2], [], [3]]`.
+    ///
+    /// Values without the group delimiter are not grouped, which keeps flat
+    /// sequences working. Add a trailing group delimiter like `1,2;` to read
+    /// a single group.
+    pub fn group_delimiter(mut self, delimiter: char) -> Self {
+        self.opts.group_delimiter = Some(delimiter);
+        self
+    }
+
+    /// Set when untyped targets see a delimited value as a sequence.
+    ///
+    /// Defaults to [`SeqInference::Always`], so `serde_json::Value` sees
+    /// `Hello, test` as `["Hello", "test"]`. Use
+    /// [`SeqInference::OnlyWhenMultipleCommas`] to keep it a string while
+    /// `1,2,3` is still a sequence.
+    pub fn seq_inference(mut self, policy: SeqInference) -> Self {
+        self.opts.seq_inference = policy;
+        self
+    }
+
+    /// Set how bytes fields like `serde_bytes::ByteBuf` read their values.
+    ///
+    /// Defaults to [`ByteEncoding::Raw`], so `KEY=hello` is `b"hello"`.
+    /// Encoded values like hex and base64 are decoded with the related
+    /// features enabled, which suits binary secrets.
+    pub fn byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.opts.byte_encoding = encoding;
+        self
+    }
+
+    /// Match enum variants case-insensitively or not.
+    ///
+    /// Disabled by default, so `LEVEL=info` doesn't match variant `Info`.
+    /// Enable it to match variants ignoring case if no variant matches
+    /// exactly, so variants `X` and `x` can still be told apart.
+    pub fn enum_case_insensitive(mut self, enable: bool) -> Self {
+        self.opts.enum_case_insensitive = enable;
+        self
+    }
+
+    /// Read sequence values starting with `[` as JSON arrays or not.
+    ///
+    /// Disabled by default, so `SERVERS=[{"host":"a"}]` is split by the
+    /// list delimiter like other values. Enable it to parse the value as a
+    /// JSON array instead, whose elements can be structs. Invalid JSON is an
+    /// error then.
+    ///
+    /// Enabled by the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn json_seq(mut self, enable: bool) -> Self {
+        self.opts.json_seq = enable;
+        self
+    }
+
+    /// Infer sequences for untyped targets or not.
+    ///
+    /// A shorthand of [`seq_inference`](EnvDeserializer::seq_inference) with
+    /// [`SeqInference::Always`] or [`SeqInference::Never`]. Typed sequence
+    /// fields like `Vec<u32>` always work.
+    pub fn auto_seq_in_any(self, enable: bool) -> Self {
+        self.seq_inference(if enable {
+            SeqInference::Always
+        } else {
+            SeqInference::Never
+        })
+    }
+
+    /// Read struct fields from files pointed by `KEY_FILE` or not.
+    ///
+    /// Disabled by default. Enable it to read field `db_password` from the
+    /// file at `DB_PASSWORD_FILE` if `DB_PASSWORD` is unset, which is common
+    /// for secrets mounted in containers. The file content is trimmed.
+    ///
+    /// `DB_PASSWORD` always wins if both are set.
+    pub fn read_file_keys(mut self, enable: bool) -> Self {
+        self.opts.read_file_keys = enable;
+        self
+    }
+
+    /// Let uppercase keys win over others differing only by case or not.
+    ///
+    /// Keys are lowercased, so `PATH` and `path` are the same field. Enabled
+    /// by default, so `PATH` wins no matter where it is. Disable it to let
+    /// the last one win for iterators like
+    /// [`from_iter`](EnvDeserializer::from_iter).
+    ///
+    /// Env vars and maps have no defined order, so uppercase keys always win
+    /// for them.
+    pub fn uppercase_wins(mut self, enable: bool) -> Self {
+        self.opts.uppercase_wins = enable;
+        self
+    }
+
+    /// Set a hook to redact values in [`ConfigReport`].
+    ///
+    /// The hook is called with the env key of every field read from env,
+    /// and the value is hidden if it returns `true`.
+    pub fn redact(mut self, f: fn(&str) -> bool) -> Self {
+        self.opts.redact = Some(f);
+        self
+    }
+
+    /// Collect vars, with uppercase keys moved last if they should win.
+    fn ordered<K, V>(
+        &self,
+        iter: impl IntoIterator<Item = (K, V)>,
+        is_upper: impl Fn(&K) -> bool,
+    ) -> Vec<(K, V)> {
+        let mut vars: Vec<_> = iter.into_iter().collect();
+        if self.opts.uppercase_wins {
+            vars.sort_by_key(|(k, _)| is_upper(k));
+        }
+        vars
+    }
+
+    fn deserialize<T>(&self, node: Node) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        T::deserialize(Deserializer::new(node, Context::new(&self.opts)))
+    }
+
+    /// Deserialize into struct via env.
+    pub fn from_env<T>(&self) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_env())
+    }
+
+    /// Deserialize into struct via env, with defaults filling unset keys.
+    ///
+    /// See [`from_env_with_defaults`](crate::from_env_with_defaults) for
+    /// details.
+    pub fn from_env_with_defaults<Iter, S, T>(&self, defaults: Iter) -> Result<T, Error>
+    where
+        Iter: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+        T: de::DeserializeOwned,
+    {
+        let defaults = self
+            .ordered(defaults, |k| is_uppercase(k.as_ref()))
+            .into_iter()
+            .map(|(k, v)| (k.as_ref().to_string(), v.as_ref().to_string()));
+        // Later keys win, so env goes after defaults. Empty env values are
+        // skipped if they are the same as unset.
+        let env = env_vars_filtered(|_| true)
+            .into_iter()
+            .filter(|(_, v)| !(self.opts.empty_string_is_none && v.is_empty()));
+        self.deserialize(Node::from_iter(defaults.chain(env)))
+    }
+
+    /// Deserialize into struct via env, with a report of the loaded fields.
+    pub fn from_env_with_report<T>(&self) -> Result<(T, ConfigReport), Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        let report = RefCell::new(ConfigReport::default());
+        let ctx = Context::with_report(&self.opts, &report);
+        let t = T::deserialize(Deserializer::new(Node::from_env(), ctx))?;
+        Ok((t, report.into_inner()))
+    }
+
+    /// Deserialize into struct via env, with the env keys read.
+    pub fn from_env_tracked<T>(&self) -> Result<(T, Vec<String>), Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        let (t, report) = self.from_env_with_report()?;
+        let keys = report
+            .entries
+            .into_iter()
+            .filter(|v| v.source == Source::Env)
+            .map(|v| v.key)
+            .collect();
+        Ok((t, keys))
+    }
+
+    /// Deserialize into struct via env, reporting all missing required
+    /// fields at once.
+    ///
+    /// Env is deserialized once if no field is missing. Serde stops at the
+    /// first missing field, so on failure env is deserialized again with
+    /// placeholders for the missing fields found so far, until no more are
+    /// found. These passes never read `KEY_FILE` files.
+    ///
+    /// Only fields serde requires are listed, while `Option` and
+    /// `#[serde(default)]` fields are not.
+    pub fn try_from_env<T>(&self) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        let node = Node::from_env();
+        let mut keys = HashSet::new();
+        let mut missing: Vec<MissingField> = Vec::new();
+
+        loop {
+            let ctx = Context::with_missing(&self.opts, &keys);
+            let err = match T::deserialize(Deserializer::borrowed(&node, ctx)) {
+                Ok(v) if missing.is_empty() => return Ok(v),
+                Ok(_) => break,
+                Err(err) => err,
+            };
+
+            match err.missing_field() {
+                Some(field) if !keys.contains(field.path()) => {
+                    // Fields of a missing struct are listed instead of the
+                    // struct itself.
+                    let key = field.path();
+                    missing.retain(|v| !key.starts_with(&format!("{}_", v.path())));
+                    keys.insert(key.to_string());
+                    missing.push(field.clone());
+                }
+                _ if missing.is_empty() => return Err(err),
+                // Placeholders may fail checks like validators, which are
+                // left to the pass with every field set.
+                _ => break,
+            }
+        }
+
+        missing.sort_by(|a, b| a.path().cmp(b.path()));
+        Err(Error::missing_fields(missing))
+    }
+
+    /// Check that env can be deserialized into `T`, discarding the value.
+    pub fn validate_env<T>(&self) -> Result<(), Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_env::<T>().map(|_| ())
+    }
+
+    /// Deserialize into struct via env vars whose key matches the predicate.
+    pub fn from_env_filtered<F, T>(&self, predicate: F) -> Result<T, Error>
+    where
+        F: Fn(&str) -> bool,
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_env_filtered(predicate))
+    }
+
+    /// Deserialize into struct via env with a prefix.
+    pub fn from_prefix<T>(&self, prefix: &str) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_env_with_prefix(
+            prefix,
+            &self.opts.prefix_separator,
+        ))
+    }
+
+    /// Deserialize into struct via env, falling back to `T::default()`.
+    ///
+    /// See [`from_env_or_default`](crate::from_env_or_default) for details.
+    pub fn from_env_or_default<T>(&self) -> T
+    where
+        T: de::DeserializeOwned + Default,
+    {
+        self.deserialize_or_default(Node::from_env())
+    }
+
+    /// Deserialize into struct via env with a prefix, falling back to
+    /// `T::default()`.
+    ///
+    /// See [`from_env_or_default`](crate::from_env_or_default) for details.
+    pub fn from_prefix_or_default<T>(&self, prefix: &str) -> T
+    where
+        T: de::DeserializeOwned + Default,
+    {
+        self.deserialize_or_default(Node::from_env_with_prefix(
+            prefix,
+            &self.opts.prefix_separator,
+        ))
+    }
+
+    fn deserialize_or_default<T>(&self, node: Node) -> T
+    where
+        T: de::DeserializeOwned + Default,
+    {
+        if node.is_empty() {
+            return T::default();
+        }
+        self.deserialize(node).unwrap_or_default()
+    }
+
+    /// Deserialize into struct via env with several fallback prefixes.
+    ///
+    /// Earlier prefixes take precedence over later ones.
+    pub fn from_prefixes<T>(&self, prefixes: &[&str]) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_env_with_prefixes(
+            prefixes,
+            &self.opts.prefix_separator,
+        ))
+    }
+
+    /// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
+    /// representing keys and values.
+    pub fn from_iter<Iter, S, T>(&self, iter: Iter) -> Result<T, Error>
+    where
+        Iter: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+        T: de::DeserializeOwned,
+    {
+        let vars = self.ordered(iter, |k| is_uppercase(k.as_ref()));
+        self.deserialize(Node::from_iter(vars))
+    }
+
+    /// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
+    /// representing keys and values, with a prefix.
+    pub fn from_iter_with_prefix<Iter, S, T>(&self, iter: Iter, prefix: &str) -> Result<T, Error>
+    where
+        Iter: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+        T: de::DeserializeOwned,
+    {
+        let vars = self.ordered(iter, |k| is_uppercase(k.as_ref()));
+        self.deserialize(Node::from_iter_with_prefix(
+            vars,
+            prefix,
+            &self.opts.prefix_separator,
+        ))
+    }
+
+    /// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
+    /// representing keys and values, with keys rewritten by `f`.
+    pub fn from_iter_with_key_map<Iter, S, F, T>(&self, iter: Iter, f: F) -> Result<T, Error>
+    where
+        Iter: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+        F: Fn(&str) -> Option<String>,
+        T: de::DeserializeOwned,
+    {
+        let vars = self.ordered(iter, |k| is_uppercase(k.as_ref()));
+        self.deserialize(Node::from_iter_with_key_map(vars, f))
+    }
+
+    /// Deserialize into struct via a map of keys and values.
+    pub fn from_map<T>(&self, map: HashMap<String, String>) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_map(map, None, &self.opts.prefix_separator))
+    }
+
+    /// Deserialize into struct via a map of keys and values, with a prefix.
+    pub fn from_map_with_prefix<T>(
+        &self,
+        map: HashMap<String, String>,
+        prefix: &str,
+    ) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(Node::from_map(
+            map,
+            Some(prefix),
+            &self.opts.prefix_separator,
+        ))
+    }
+
+    /// Deserialize into struct via a dotenv file like `.env`.
+    pub fn from_file<T>(&self, path: impl AsRef<Path>) -> Result<T, Error>
+    where
+        T: de::DeserializeOwned,
+    {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|err| Error::io(None, path.display().to_string(), err))?;
+        self.from_iter(dotenv::parse(&src, &path.display().to_string())?)
+    }
+
+    /// Deserialize into struct via dotenv content read from a reader.
+    pub fn from_reader<R, T>(&self, mut r: R) -> Result<T, Error>
+    where
+        R: Read,
+        T: de::DeserializeOwned,
+    {
+        let mut src = String::new();
+        r.read_to_string(&mut src)
+            .map_err(|err| Error::io(None, "<reader>".to_string(), err))?;
+        self.from_iter(dotenv::parse(&src, "<reader>")?)
+    }
+
+    /// Deserialize into struct via a node, borrowing from it if possible.
+    pub fn from_node<'de, T>(&self, node: &'de Node) -> Result<T, Error>
+    where
+        T: de::Deserialize<'de>,
+    {
+        T::deserialize(Deserializer::borrowed(node, Context::new(&self.opts)))
+    }
+
+    /// Deserialize a single field like `port` or `db_url` from a node.
+    ///
+    /// A missing field is `None` for `Option`, and an error otherwise.
+    pub fn deserialize_field<'de, T>(&self, node: &'de Node, field: &str) -> Result<T, Error>
+    where
+        T: de::Deserialize<'de>,
+    {
+        let key = env_key(field);
+        match node.get(&key) {
+            Some(v) if !(self.opts.empty_string_is_none && v.is_empty()) => {
+                T::deserialize(Deserializer::borrowed(v, Context::new(&self.opts)))
+                    .map_err(|err| err.with_field(field))
+            }
+            _ => T::deserialize(de::IntoDeserializer::<Error>::into_deserializer(()))
+                .map_err(|_| Error::missing_field_with_key(field, key)),
+        }
+    }
+
+    /// Deserialize into struct via an iterable of `(AsRef<OsStr>, AsRef<OsStr>)`
+    /// representing keys and values.
+    pub fn from_iter_os<Iter, K, V, T>(&self, iter: Iter) -> Result<T, Error>
+    where
+        Iter: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+        T: de::DeserializeOwned,
+    {
+        let vars = self.ordered(iter, |k| is_uppercase(&k.as_ref().to_string_lossy()));
+        self.deserialize(Node::from_iter_os(vars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        tags: Vec<String>,
+        name: Option<String>,
+        db_url: String,
+    }
+
+    #[test]
+    fn test_multiple_options() {
+        let de = EnvDeserializer::new()
+            .trim_seq_elements(false)
+            .empty_string_is_none(false)
+            .prefix_separator("__");
+
+        let vars = [
+            ("APP__TAGS", "a, b"),
+            ("APP__NAME", ""),
+            ("APP__DB_URL", "localhost"),
+            ("APP_DB_URL", "ignored"),
+        ];
+        let t: Test = de.from_iter_with_prefix(vars, "APP").expect("must success");
+        assert_eq!(
+            t,
+            Test {
+                tags: vec!["a".to_string(), " b".to_string()],
+                name: Some(String::new()),
+                db_url: "localhost".to_string(),
+            }
+        );
+
+        // Builder can be reused.
+        let vars = [("APP__TAGS", "c"), ("APP__DB_URL", "remote")];
+        let t: Test = de.from_iter_with_prefix(vars, "APP").expect("must success");
+        assert_eq!(t.name, None);
+        assert_eq!(t.db_url, "remote");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFile {
+        db_password: String,
+        db_user: String,
+    }
+
+    /// Temp file removed on drop, even if the test fails.
+    struct TempFile(std::path::PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_read_file_keys() {
+        let path = std::env::temp_dir().join(format!("serde-env-{}", std::process::id()));
+        std::fs::write(&path, "s3cret\n").expect("write temp file");
+        let temp = TempFile(path.clone());
+        let file = path.to_str().unwrap();
+
+        let de = EnvDeserializer::new().read_file_keys(true);
+        let t: TestFile = de
+            .from_iter([("DB_PASSWORD_FILE", file), ("DB_USER", "admin")])
+            .expect("must success");
+        assert_eq!(t.db_password, "s3cret");
+        assert_eq!(t.db_user, "admin");
+
+        // Value in env wins.
+        let t: TestFile = de
+            .from_iter([
+                ("DB_PASSWORD", "plain"),
+                ("DB_PASSWORD_FILE", file),
+                ("DB_USER", "admin"),
+            ])
+            .expect("must success");
+        assert_eq!(t.db_password, "plain");
+
+        // Disabled by default, so `DB_PASSWORD_FILE` is a plain nested key.
+        let err = EnvDeserializer::new()
+            .from_iter::<_, _, TestFile>([("DB_PASSWORD_FILE", file), ("DB_USER", "admin")])
+            .expect_err("must fail");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `db_password`: expected a scalar but found nested keys \
+             DB_PASSWORD_FILE"
+        );
+
+        drop(temp);
+
+        let r: Result<TestFile, _> =
+            de.from_iter([("DB_PASSWORD_FILE", file), ("DB_USER", "admin")]);
+        let err = r.expect_err("file is removed");
+        assert!(
+            err.to_string()
+                .starts_with(&format!("read `DB_PASSWORD_FILE` from {file}: ")),
+            "{err}"
+        );
+        let source = std::error::Error::source(&err).expect("source must exist");
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn test_seq_inference() {
+        use serde_json::{json, Value};
+
+        let vars = [("A", "Hello, test"), ("B", "1,2,3"), ("C", "a,")];
+        let cases = [
+            (
+                SeqInference::Always,
+                json!({ "a": ["Hello", "test"], "b": [1, 2, 3], "c": ["a"] }),
+            ),
+            (
+                SeqInference::Never,
+                json!({ "a": "Hello, test", "b": "1,2,3", "c": "a," }),
+            ),
+            (
+                SeqInference::OnlyWhenMultipleCommas,
+                json!({ "a": "Hello, test", "b": [1, 2, 3], "c": "a," }),
+            ),
+        ];
+
+        for (policy, expected) in cases {
+            let t: Value = EnvDeserializer::new()
+                .seq_inference(policy)
+                .from_iter(vars)
+                .expect("must success");
+            assert_eq!(t, expected, "{policy:?}");
+        }
+
+        // Typed fields are not affected.
+        let t: Test = EnvDeserializer::new()
+            .seq_inference(SeqInference::Never)
+            .from_iter([("TAGS", "a,b"), ("DB_URL", "localhost")])
+            .expect("must success");
+        assert_eq!(t.tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_auto_seq_in_any() {
+        use serde_json::{json, Value};
+
+        let vars = [("A", "Hello, test"), ("B", "1,2")];
+
+        let t: Value = EnvDeserializer::new()
+            .auto_seq_in_any(true)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t, json!({ "a": ["Hello", "test"], "b": [1, 2] }));
+
+        let t: Value = EnvDeserializer::new()
+            .auto_seq_in_any(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t, json!({ "a": "Hello, test", "b": "1,2" }));
+    }
+
+    #[test]
+    fn test_uppercase_wins() {
+        #[derive(Deserialize, Debug)]
+        struct Test {
+            path: String,
+        }
+
+        let vars = [("PATH", "/upper"), ("path", "/lower"), ("Path", "/mixed")];
+
+        let t: Test = EnvDeserializer::new()
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t.path, "/upper");
+
+        let t: Test = EnvDeserializer::new()
+            .uppercase_wins(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t.path, "/mixed");
+
+        let map = vars.map(|(k, v)| (k.to_string(), v.to_string())).into();
+        let t: Test = EnvDeserializer::new()
+            .uppercase_wins(false)
+            .from_map(map)
+            .expect("must success");
+        assert_eq!(t.path, "/upper");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestBytes {
+        key: serde_bytes::ByteBuf,
+    }
+
+    #[test]
+    fn test_byte_encoding_raw() {
+        let t: TestBytes = EnvDeserializer::new()
+            .from_iter([("KEY", "68656c6c6f")])
+            .expect("must success");
+        assert_eq!(t.key.as_slice(), b"68656c6c6f");
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn test_byte_encoding_hex() {
+        let de = EnvDeserializer::new().byte_encoding(ByteEncoding::Hex);
+        let t: TestBytes = de.from_iter([("KEY", "68656C6C6F")]).expect("must success");
+        assert_eq!(t.key.as_slice(), b"hello");
+
+        let err = de
+            .from_iter::<_, _, TestBytes>([("KEY", "xyz")])
+            .expect_err("invalid hex");
+        assert!(err.to_string().contains("`key`"), "{err}");
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_byte_encoding_base64() {
+        let de = EnvDeserializer::new().byte_encoding(ByteEncoding::Base64);
+        let t: TestBytes = de.from_iter([("KEY", "aGVsbG8=")]).expect("must success");
+        assert_eq!(t.key.as_slice(), b"hello");
+
+        let node: Node = [("KEY", "aGVsbG8=")].into_iter().collect();
+        let t: TestBytes = de.from_node(&node).expect("must success");
+        assert_eq!(t.key.as_slice(), b"hello");
+    }
+}
@@ -0,0 +1,146 @@
+//! RFC 3339 timestamps like `START_AT=2024-01-01T00:00:00Z`.
+//!
+//! Enabled by the `chrono` or `time` feature, which decides the supported
+//! types, see [`Rfc3339`].
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "chrono")]
+//! # {
+//! use chrono::{DateTime, Utc};
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::datetime::deserialize")]
+//!     start_at: DateTime<Utc>,
+//! }
+//!
+//! let t: Test = from_iter([("START_AT", "2024-01-01T08:00:00+08:00")])
+//!     .expect("deserialize from iter");
+//! assert_eq!(t.start_at.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+//! # }
+//! ```
+
+use serde::Deserializer;
+
+use crate::error::{parse_str, Error};
+
+/// Datetime types that can be parsed from RFC 3339.
+///
+/// - `chrono::DateTime<Utc>` and `chrono::DateTime<FixedOffset>` with the
+///   `chrono` feature.
+/// - `time::OffsetDateTime` with the `time` feature.
+pub trait Rfc3339: Sized {
+    /// Parse an RFC 3339 timestamp like `2024-01-01T00:00:00Z`.
+    fn parse_rfc3339(s: &str) -> Result<Self, Error>;
+}
+
+#[cfg(feature = "chrono")]
+impl Rfc3339 for chrono::DateTime<chrono::FixedOffset> {
+    fn parse_rfc3339(s: &str) -> Result<Self, Error> {
+        chrono::DateTime::parse_from_rfc3339(s).map_err(Error::new)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Rfc3339 for chrono::DateTime<chrono::Utc> {
+    fn parse_rfc3339(s: &str) -> Result<Self, Error> {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .map(|v| v.with_timezone(&chrono::Utc))
+            .map_err(Error::new)
+    }
+}
+
+#[cfg(feature = "time")]
+impl Rfc3339 for time::OffsetDateTime {
+    fn parse_rfc3339(s: &str) -> Result<Self, Error> {
+        time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339)
+            .map_err(Error::new)
+    }
+}
+
+/// Parse an RFC 3339 timestamp like `2024-01-01T00:00:00Z`.
+pub fn parse<T: Rfc3339>(s: &str) -> Result<T, Error> {
+    T::parse_rfc3339(s.trim())
+}
+
+/// Deserialize an RFC 3339 timestamp.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::datetime::deserialize")]`.
+pub fn deserialize<'de, D, T>(d: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Rfc3339,
+{
+    parse_str(d, parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::error::ErrorKind;
+    use crate::from_iter;
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_deserialize_chrono() {
+        use chrono::{DateTime, FixedOffset, Utc};
+
+        #[derive(Deserialize, Debug)]
+        struct Test {
+            #[serde(deserialize_with = "super::deserialize")]
+            start_at: DateTime<Utc>,
+            #[serde(deserialize_with = "super::deserialize")]
+            end_at: DateTime<FixedOffset>,
+        }
+
+        let t: Test = from_iter([
+            ("START_AT", "2024-01-01T00:00:00Z"),
+            ("END_AT", "2024-01-02T00:00:00+08:00"),
+        ])
+        .expect("must success");
+        assert_eq!(t.start_at.timestamp(), 1704067200);
+        assert_eq!(t.end_at.timestamp(), 1704067200 + 16 * 3600);
+        assert_eq!(t.end_at.offset().local_minus_utc(), 8 * 3600);
+
+        let err = from_iter::<_, _, Test>([("START_AT", "2024-01-01"), ("END_AT", "")])
+            .expect_err("must fail");
+        assert!(err.to_string().contains("premature end of input"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("start_at"));
+                assert!(source.downcast_ref::<chrono::ParseError>().is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_deserialize_time() {
+        use time::OffsetDateTime;
+
+        #[derive(Deserialize, Debug)]
+        struct Test {
+            #[serde(deserialize_with = "super::deserialize")]
+            start_at: OffsetDateTime,
+        }
+
+        let t: Test = from_iter([("START_AT", "2024-01-01T08:00:00+08:00")]).expect("must success");
+        assert_eq!(t.start_at.unix_timestamp(), 1704067200);
+
+        let err = from_iter::<_, _, Test>([("START_AT", "tomorrow")]).expect_err("must fail");
+        assert!(err.to_string().contains("year"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("start_at"));
+                assert!(source.downcast_ref::<time::error::Parse>().is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+    }
+}
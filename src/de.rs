@@ -1,9 +1,17 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::num::ParseIntError;
+use std::str::FromStr;
 
+use serde::de::value::{MapDeserializer, SeqDeserializer};
 use serde::de::{DeserializeSeed, IntoDeserializer, SeqAccess, Visitor};
 use serde::{de, forward_to_deserialize_any};
 
-use crate::error::Error;
+use crate::builder::{Context, EnvDeserializer};
+use crate::error::{Error, ErrorKind};
+use crate::report::ConfigReport;
 use crate::value::Node;
 
 /// Deserialize into struct via env.
@@ -32,10 +40,273 @@ pub fn from_env<T>() -> Result<T, Error>
 where
     T: de::DeserializeOwned,
 {
-    T::deserialize(Deserializer(Node::from_env()))
+    EnvDeserializer::new().from_env()
 }
+
+/// Deserialize into struct via env, reporting all missing required fields
+/// at once.
+///
+/// [`from_env`] fails on the first missing field, while this function
+/// returns an error listing all of them with the env keys looked for, see
+/// [`Error::missing`].
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::try_from_env;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     not_exist_a: String,
+///     not_exist_b: String,
+/// }
+///
+/// let err = try_from_env::<Test>().expect_err("must fail");
+/// let keys: Vec<_> = err.missing().iter().map(|v| v.key()).collect();
+/// assert_eq!(keys, ["NOT_EXIST_A", "NOT_EXIST_B"]);
+/// ```
+pub fn try_from_env<T>() -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().try_from_env()
+}
+
+/// Check that env can be deserialized into `T`, discarding the value.
+///
+/// Useful for flows like `--check-config`. It fails on the first error like
+/// [`from_env`], use [`try_from_env`] to list all missing fields instead.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::validate_env;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     not_exist: u16,
+/// }
+///
+/// let err = validate_env::<Test>().expect_err("must fail");
+/// assert_eq!(err.to_string(), "missing field `not_exist`");
+/// ```
+pub fn validate_env<T>() -> Result<(), Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().validate_env::<T>()
+}
+
+/// Deserialize into struct via env, with a report of the loaded fields.
+///
+/// See [`ConfigReport`] for details, and use [`EnvDeserializer::redact`] to
+/// hide secrets in the report.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::{from_env_with_report, Source};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     #[cfg(windows)]
+///     #[serde(rename = "userprofile")]
+///     home: String,
+///     #[cfg(not(windows))]
+///     home: String,
+///     #[serde(default)]
+///     not_exist: String,
+/// }
+///
+/// let (t, report) = from_env_with_report::<Test>().expect("deserialize from env");
+/// assert_eq!(report.get("NOT_EXIST").unwrap().source, Source::Default);
+/// println!("{report}");
+/// ```
+pub fn from_env_with_report<T>() -> Result<(T, ConfigReport), Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_env_with_report()
+}
+
+/// Deserialize into struct via env, with the env keys read, like `D_AA`.
+///
+/// Keys are listed in visiting order. It's a lighter version of
+/// [`from_env_with_report`] that drops defaulted fields and values.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_tracked;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     #[cfg(windows)]
+///     #[serde(rename = "userprofile")]
+///     home: String,
+///     #[cfg(not(windows))]
+///     home: String,
+///     #[serde(default)]
+///     not_exist: String,
+/// }
+///
+/// let (_, keys) = from_env_tracked::<Test>().expect("deserialize from env");
+/// assert_eq!(keys.len(), 1);
+/// ```
+pub fn from_env_tracked<T>() -> Result<(T, Vec<String>), Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_env_tracked()
+}
+
+/// Deserialize into struct via env vars whose key matches the predicate.
+///
+/// The predicate is called with the original env key like `APP_SECRET`,
+/// and other vars never enter the tree.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_filtered;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     test_filtered_name: Option<String>,
+///     test_filtered_secret: Option<String>,
+/// }
+///
+/// temp_env::with_vars(
+///     [
+///         ("TEST_FILTERED_NAME", Some("app")),
+///         ("TEST_FILTERED_SECRET", Some("hunter2")),
+///     ],
+///     || {
+///         let t: Test = from_env_filtered(|k| !k.contains("SECRET"))
+///             .expect("deserialize from env");
+///
+///         assert_eq!(t.test_filtered_name.as_deref(), Some("app"));
+///         assert_eq!(t.test_filtered_secret, None);
+///     },
+/// );
+/// ```
+pub fn from_env_filtered<F, T>(predicate: F) -> Result<T, Error>
+where
+    F: Fn(&str) -> bool,
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_env_filtered(predicate)
+}
+
+/// Deserialize into struct via env, falling back to `T::default()` on any
+/// error.
+///
+/// Both missing fields and invalid values fall back, so a typo like
+/// `PORT=80a` is silently ignored. Use [`from_env`] if errors matter.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_or_default;
+///
+/// #[derive(Debug, Default, Deserialize)]
+/// struct Test {
+///     not_exist: u16,
+/// }
+///
+/// let t: Test = from_env_or_default();
+/// assert_eq!(t.not_exist, 0);
+/// ```
+pub fn from_env_or_default<T>() -> T
+where
+    T: de::DeserializeOwned + Default,
+{
+    EnvDeserializer::new().from_env_or_default()
+}
+
+/// Deserialize into struct via env, with defaults filling unset keys.
+///
+/// Defaults are given like env vars, and env always wins over them. Empty
+/// env values like `PORT=` are unset unless
+/// [`empty_string_is_none`](EnvDeserializer::empty_string_is_none) is
+/// disabled, so defaults fill them too.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_with_defaults;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     serde_env_defaults_host: String,
+///     serde_env_defaults_port: u16,
+/// }
+///
+/// temp_env::with_var("SERDE_ENV_DEFAULTS_PORT", Some("8080"), || {
+///     let t: Test = from_env_with_defaults([
+///         ("SERDE_ENV_DEFAULTS_HOST", "localhost"),
+///         ("SERDE_ENV_DEFAULTS_PORT", "80"),
+///     ])
+///     .expect("deserialize from env");
+///
+///     assert_eq!(t.serde_env_defaults_host, "localhost");
+///     assert_eq!(t.serde_env_defaults_port, 8080);
+/// });
+/// ```
+pub fn from_env_with_defaults<Iter, S, T>(defaults: Iter) -> Result<T, Error>
+where
+    Iter: IntoIterator<Item = (S, S)>,
+    S: AsRef<str>,
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_env_with_defaults(defaults)
+}
+
+/// Deserialize into struct via env with a prefix, falling back to
+/// `T::default()` on any error.
+///
+/// `T::default()` is returned directly if no env var has the prefix, even
+/// if `T` could be deserialized from nothing.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_with_prefix_or_default;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     port: Option<u16>,
+/// }
+///
+/// impl Default for Test {
+///     fn default() -> Self {
+///         Self { port: Some(8080) }
+///     }
+/// }
+///
+/// let t: Test = from_env_with_prefix_or_default("NOT_EXIST_APP");
+/// assert_eq!(t.port, Some(8080));
+/// ```
+pub fn from_env_with_prefix_or_default<T>(prefix: &str) -> T
+where
+    T: de::DeserializeOwned + Default,
+{
+    EnvDeserializer::new().from_prefix_or_default(prefix)
+}
+
 /// Deserialize into struct via env with a prefix.
 ///
+/// The prefix is matched case-insensitively like keys, so `TEST_ENV` matches
+/// `test_env_home` too.
+///
 /// # Examples
 ///
 /// ```
@@ -67,7 +338,48 @@ pub fn from_env_with_prefix<T>(prefix: &str) -> Result<T, Error>
 where
     T: de::DeserializeOwned,
 {
-    T::deserialize(Deserializer(Node::from_env_with_prefix(prefix)))
+    EnvDeserializer::new().from_prefix(prefix)
+}
+
+/// Deserialize into struct via env with several fallback prefixes.
+///
+/// Every field is looked up under each prefix in order, and the first
+/// prefix that has the field wins.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_env_with_prefixes;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Test {
+///     home: String,
+///     path: String,
+/// }
+/// temp_env::with_vars(
+///     [
+///         ("TEST_NEW_HOME", Some("/new")),
+///         ("TEST_OLD_HOME", Some("/old")),
+///         ("TEST_OLD_PATH", Some("foo:bar")),
+///     ],
+///     || {
+///         let t: Test = from_env_with_prefixes(&["TEST_NEW", "TEST_OLD"])
+///             .expect("deserialize from env");
+///
+///         let result = Test {
+///             home: "/new".to_string(),
+///             path: "foo:bar".to_string(),
+///         };
+///         assert_eq!(t, result);
+///     },
+/// );
+/// ```
+pub fn from_env_with_prefixes<T>(prefixes: &[&str]) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_prefixes(prefixes)
 }
 
 /// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
@@ -104,7 +416,7 @@ where
     S: AsRef<str>,
     T: de::DeserializeOwned,
 {
-    T::deserialize(Deserializer(Node::from_iter(iter)))
+    EnvDeserializer::new().from_iter(iter)
 }
 
 /// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
@@ -141,248 +453,962 @@ where
     S: AsRef<str>,
     T: de::DeserializeOwned,
 {
-    T::deserialize(Deserializer(Node::from_iter_with_prefix(iter, prefix)))
+    EnvDeserializer::new().from_iter_with_prefix(iter, prefix)
 }
 
-struct Deserializer(Node);
-
-impl<'de> de::Deserializer<'de> for Deserializer {
-    type Error = Error;
+/// Deserialize into struct via a dotenv file like `.env`.
+///
+/// Every line is a `KEY=VALUE` pair, optionally prefixed by `export `, while
+/// blank lines and comments starting with `#` are skipped. Malformed lines
+/// fail with their line number, like
+/// `invalid syntax at config.env:7: expected KEY=VALUE`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use serde::Deserialize;
+/// use serde_env::from_file;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     port: u16,
+/// }
+///
+/// let t: Test = from_file(".env").expect("deserialize from file");
+/// println!("{:?}", t);
+/// ```
+pub fn from_file<T>(path: impl AsRef<std::path::Path>) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_file(path)
+}
 
-    /// https://serde.rs/impl-deserialize.html
-    /// The various other deserialize_* methods. Non-self-describing formats like Postcard need
-    /// to be told what is in the input in order to deserialize it.
-    /// The deserialize_* methods are hints to the deserializer for how to interpret the next
-    /// piece of input. Non-self-describing formats are not able to deserialize something like serde_json::Value
-    /// which relies on Deserializer::deserialize_any.
-    ///
-    ///
-    /// support:
-    /// 1. array: 1,2,3
-    /// 2. bool: true or false or True or False
-    /// 3. number: must be valid u64 or i64
-    /// 4. string: "hello"
-    /// 5. Enums with unit variants see <https://github.com/Xuanwo/serde-env/pull/16>
-    fn deserialize_any<V>(self, vis: V) -> Result<V::Value, Self::Error>
-    where
-        V: Visitor<'de>,
-    {
-        // dbg!(&self.0.value());
-        let bytes = self.0.value().as_bytes();
-        if bytes.is_empty() {
-            return vis.visit_none();
-        }
-        let first = bytes[0];
+/// Deserialize into struct via dotenv content read from a reader, see
+/// [`from_file`] for the format.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_reader;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let src = "# server\nHOST=localhost\nPORT=8080\n";
+/// let t: Test = from_reader(src.as_bytes()).expect("deserialize from reader");
+/// assert_eq!(t.port, 8080);
+/// ```
+pub fn from_reader<R, T>(r: R) -> Result<T, Error>
+where
+    R: std::io::Read,
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_reader(r)
+}
 
-        match first {
-            _ if self.0.value().contains(',') => {
-                return self.deserialize_seq(vis);
-            }
-            b'0'..=b'9' => {
-                if bytes.iter().all(|&b| b.is_ascii_digit()) {
-                    return match self.0.value().parse::<u64>() {
-                        Ok(v) => vis.visit_u64(v),
-                        Err(_) => self.deserialize_str(vis),
-                    };
-                }
-            }
-            b'-' => {
-                if bytes.iter().skip(1).all(|&b| b.is_ascii_digit()) {
-                    return match self.0.value().parse::<i64>() {
-                        Ok(v) => vis.visit_i64(v),
-                        Err(_) => self.deserialize_str(vis),
-                    };
-                }
-            }
-            b't' | b'f' | b'T' | b'F' => {
-                if bytes.eq_ignore_ascii_case(b"true") {
-                    return vis.visit_bool(true);
-                } else if bytes.eq_ignore_ascii_case(b"false") {
-                    return vis.visit_bool(false);
-                }
-            }
-            _ => {}
-        };
-        self.deserialize_str(vis)
-    }
+/// Deserialize into struct via an iterable of `(AsRef<str>, AsRef<str>)`
+/// representing keys and values, with keys rewritten by `f` first.
+///
+/// `f` returns the new key like `db_host`, or `None` to drop the key.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::from_iter_with_key_map;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Test {
+///     db: Db,
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Db {
+///     host: String,
+/// }
+///
+/// let vars = [("MYAPP__DB__HOST", "localhost"), ("OTHER", "x")];
+/// let t: Test = from_iter_with_key_map(vars, |k| {
+///     k.strip_prefix("MYAPP__").map(|k| k.replace("__", "_"))
+/// })
+/// .expect("deserialize from iter");
+///
+/// assert_eq!(t.db.host, "localhost");
+/// ```
+pub fn from_iter_with_key_map<Iter, S, F, T>(iter: Iter, f: F) -> Result<T, Error>
+where
+    Iter: IntoIterator<Item = (S, S)>,
+    S: AsRef<str>,
+    F: Fn(&str) -> Option<String>,
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_iter_with_key_map(iter, f)
+}
 
-    fn deserialize_bool<V>(self, vis: V) -> Result<V::Value, Self::Error>
+/// Deserialize into struct via a map of keys and values, like config
+/// loaded from a remote store.
+///
+/// It's the same as [`from_iter`], but values are moved instead of copied.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use serde::Deserialize;
+/// use serde_env::from_map;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     home: String,
+/// }
+///
+/// let map = HashMap::from([("HOME".to_string(), "/test".to_string())]);
+/// let t: Test = from_map(map).expect("deserialize from map");
+///
+/// assert_eq!(t.home, "/test");
+/// ```
+pub fn from_map<T>(map: HashMap<String, String>) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_map(map)
+}
+
+/// Deserialize into struct via a map of keys and values, with a prefix.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use serde::Deserialize;
+/// use serde_env::from_map_with_prefix;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     home: String,
+/// }
+///
+/// let map = HashMap::from([
+///     ("APP_HOME".to_string(), "/test".to_string()),
+///     ("HOME".to_string(), "/other".to_string()),
+/// ]);
+/// let t: Test = from_map_with_prefix(map, "APP").expect("deserialize from map");
+///
+/// assert_eq!(t.home, "/test");
+/// ```
+pub fn from_map_with_prefix<T>(map: HashMap<String, String>, prefix: &str) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_map_with_prefix(map, prefix)
+}
+
+/// Deserialize into struct via an iterable of `(AsRef<OsStr>, AsRef<OsStr>)`
+/// representing keys and values, for example [`std::env::vars_os`].
+///
+/// Keys are converted into UTF-8 lossily (invalid sequences are replaced
+/// with `U+FFFD`) before building the tree. Values are converted lossily
+/// for string and scalar fields too, but byte fields (which call
+/// `deserialize_bytes` or `deserialize_byte_buf`) get the raw bytes of the
+/// value untouched.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::OsString;
+///
+/// use serde::Deserialize;
+/// use serde_env::from_iter_os;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Test {
+///     home: String,
+///     path: String,
+/// }
+/// let vars = [
+///     (OsString::from("HOME"), OsString::from("/test")),
+///     (OsString::from("PATH"), OsString::from("foo:bar")),
+/// ];
+///
+/// let actual: Test = from_iter_os(vars).expect("deserialize from iter");
+///
+/// let expected = Test {
+///     home: "/test".to_string(),
+///     path: "foo:bar".to_string(),
+/// };
+///
+/// assert_eq!(actual, expected);
+/// ```
+pub fn from_iter_os<Iter, K, V, T>(iter: Iter) -> Result<T, Error>
+where
+    Iter: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+    T: de::DeserializeOwned,
+{
+    EnvDeserializer::new().from_iter_os(iter)
+}
+
+/// Deserialize into struct via a node, borrowing from it if possible.
+///
+/// Unlike other functions, the node outlives the result, so borrowed types
+/// like `&str` and `&Path` can be deserialized without allocation.
+///
+/// Nodes can be built from any source and layered via [`Node::merge`]
+/// before deserializing.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use serde::Deserialize;
+/// use serde_env::{from_node, Node};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test<'a> {
+///     #[serde(borrow)]
+///     config_dir: &'a Path,
+///     name: &'a str,
+/// }
+///
+/// let node: Node = [("CONFIG_DIR", "/etc/app"), ("NAME", "app")]
+///     .into_iter()
+///     .collect();
+/// let t: Test = from_node(&node).expect("deserialize from node");
+///
+/// assert_eq!(t.config_dir, Path::new("/etc/app"));
+/// assert_eq!(t.name, "app");
+/// ```
+pub fn from_node<'de, T>(node: &'de Node) -> Result<T, Error>
+where
+    T: de::Deserialize<'de>,
+{
+    EnvDeserializer::new().from_node(node)
+}
+
+/// Deserialize a single field like `port` or `db_url` from a node.
+///
+/// Fields can be loaded one by one this way, so that an invalid field
+/// doesn't fail the others. A missing field is `None` for `Option`, and an
+/// error otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use serde_env::{deserialize_field, Node};
+///
+/// let node: Node = [("PORT", "8080"), ("DB_URL", "abc")].into_iter().collect();
+///
+/// let port: u32 = deserialize_field(&node, "port").expect("deserialize field");
+/// assert_eq!(port, 8080);
+/// assert!(deserialize_field::<u32>(&node, "db_url").is_err());
+/// ```
+pub fn deserialize_field<'de, T>(node: &'de Node, field: &str) -> Result<T, Error>
+where
+    T: de::Deserialize<'de>,
+{
+    EnvDeserializer::new().deserialize_field(node, field)
+}
+
+/// Deserializer over a node, which is either owned or borrowed.
+///
+/// Borrowed nodes can be deserialized into borrowed types like `&str`.
+pub(crate) struct Deserializer<'de, 'a> {
+    node: Cow<'de, Node>,
+    ctx: Context<'a>,
+    /// Full key of this node, only tracked while reporting.
+    path: String,
+    /// Set for values of map entries, and notes how the value is read, see
+    /// [`mark_subtree`](Self::mark_subtree) and [`leaf`](Self::leaf).
+    entry: Option<&'a Cell<EntryRead>>,
+    /// Whether scalars are read leniently, see
+    /// [`check_scalar`](Self::check_scalar).
+    lenient: bool,
+}
+
+/// How the value of a map entry is read.
+#[derive(Debug, Default, PartialEq, Eq)]
+enum EntryRead {
+    #[default]
+    Unknown,
+    /// Read as a scalar from the nested key, like `name` of `app`.
+    Leaf(String),
+    /// Read as a struct, map or indexed sequence.
+    Subtree,
+}
+
+impl<'de, 'a> Deserializer<'de, 'a> {
+    pub(crate) fn new(node: Node, ctx: Context<'a>) -> Self {
+        Self::with_path(Cow::Owned(node), ctx, String::new())
+    }
+
+    pub(crate) fn borrowed(node: &'de Node, ctx: Context<'a>) -> Self {
+        Self::with_path(Cow::Borrowed(node), ctx, String::new())
+    }
+
+    fn with_path(node: Cow<'de, Node>, ctx: Context<'a>, path: String) -> Self {
+        Self {
+            node,
+            ctx,
+            path,
+            entry: None,
+            lenient: false,
+        }
+    }
+
+    /// Into indexed children sorted by their index.
+    fn into_indexed_children(self) -> Vec<(usize, Cow<'de, Node>)> {
+        match self.node {
+            Cow::Borrowed(node) => node
+                .indexed_children()
+                .into_iter()
+                .map(|(idx, v)| (idx, Cow::Borrowed(v)))
+                .collect(),
+            Cow::Owned(node) => node
+                .into_indexed_children()
+                .into_iter()
+                .map(|(idx, v)| (idx, Cow::Owned(v)))
+                .collect(),
+        }
+    }
+
+    /// Split value into sequence elements.
+    fn split(&self) -> impl Iterator<Item = &str> {
+        let trim = self.ctx.opts.trim_seq_elements;
+        self.node
+            .value()
+            .split(self.ctx.opts.list_delimiter)
+            .map(move |v| if trim { v.trim() } else { v })
+    }
+
+    /// Split value into groups of a nested sequence, if the group delimiter
+    /// is set and found in value.
+    ///
+    /// A trailing group delimiter is ignored, and empty groups are kept.
+    fn split_groups(&self) -> Option<Vec<&str>> {
+        let delimiter = self.ctx.opts.group_delimiter?;
+        let value = self.node.value();
+        if !value.contains(delimiter) {
+            return None;
+        }
+
+        let trim = self.ctx.opts.trim_seq_elements;
+        let groups = value
+            .strip_suffix(delimiter)
+            .unwrap_or(value)
+            .split(delimiter)
+            .map(|v| if trim { v.trim() } else { v })
+            .collect();
+        Some(groups)
+    }
+
+    /// Parse value as an integer, see [`parse_int`].
+    fn int<T: Int>(&mut self) -> Result<T, Error> {
+        parse_int(self.scalar()?).map_err(Error::new)
+    }
+
+    /// Parse value as an integer and visit it.
+    ///
+    /// Since `0` fits all integer types, a visitor rejecting it wants a
+    /// non-zero value like `NonZeroU16`, which is turned into a parse error
+    /// so that the field name is attached.
+    fn visit_int<T: Int, R>(
+        &mut self,
+        visit: impl FnOnce(T) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let v: T = self.int()?;
+        if v != T::default() {
+            return visit(v);
+        }
+        visit(v).map_err(|err| match err.kind() {
+            ErrorKind::Message(_) => Error::new(NonZero),
+            _ => err,
+        })
+    }
+
+    /// Value without matching `"` or `'` quotes around it, if quoted.
+    fn unquoted(&self) -> Option<&str> {
+        unquote(self.node.value())
+    }
+
+    /// Error if the node has both a value and children while collisions
+    /// are detected, since the target can only take one of them.
+    fn check_collision(&self, expected: &'static str) -> Result<(), Error> {
+        if self.ctx.opts.detect_collisions
+            && !self.node.value().is_empty()
+            && self.node.has_children()
+        {
+            return Err(Error::new(Collision {
+                value: self.node.value().to_string(),
+                expected,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Error if a scalar is expected but the node only has children, like
+    /// `D_AA` and `D_BB` for `d: String`, which is most likely a typo.
+    ///
+    /// Values of map entries are read from their nested key instead, see
+    /// [`leaf`](Self::leaf).
+    fn check_scalar(&mut self) -> Result<(), Error> {
+        self.leaf();
+        self.check_collision("a scalar")?;
+        if !self.lenient && !self.node.is_present() && self.node.has_children() {
+            let keys = self
+                .node
+                .flatten()
+                .into_iter()
+                .filter(|k| self.node.get(k).is_some_and(Node::is_present))
+                .collect();
+            return Err(Error::new(NestedKeys(keys)));
+        }
+        Ok(())
+    }
+
+    /// Mark the value of a map entry as read as a subtree, so that flat keys
+    /// under it are not visited as other entries.
+    fn mark_subtree(&self) {
+        if let Some(entry) = self.entry {
+            if self.node.has_children() {
+                entry.set(EntryRead::Subtree);
+            }
+        }
+    }
+
+    /// Move to the first supplied nested key if a scalar is read from a map
+    /// entry without value, so `APP_NAME=x` is entry `app_name` of
+    /// `HashMap<String, String>` instead of an empty `app`.
+    fn leaf(&mut self) {
+        let Some(entry) = self.entry else {
+            return;
+        };
+        if !self.lenient || self.node.is_present() || !self.node.has_children() {
+            return;
+        }
+
+        let skip_empty = self.ctx.opts.empty_string_is_none;
+        let Some(key) = self.node.flatten().into_iter().find(|k| {
+            self.node
+                .get(k)
+                .is_some_and(|v| v.is_present() && !(skip_empty && v.value().is_empty()))
+        }) else {
+            return;
+        };
+        let node = match &mut self.node {
+            Cow::Borrowed(node) => {
+                let node: &'de Node = node;
+                node.get(&key).map(Cow::Borrowed)
+            }
+            Cow::Owned(node) => node.take(&key).map(Cow::Owned),
+        };
+        if let Some(node) = node {
+            self.node = node;
+            self.path = self.ctx.join(&self.path, &key);
+            entry.set(EntryRead::Leaf(key));
+        }
+    }
+
+    /// Get the value for a number or bool target.
+    ///
+    /// Whitespace around value like ` 123 ` is trimmed, since it's never
+    /// meaningful for these targets.
+    fn scalar(&mut self) -> Result<&str, Error> {
+        self.check_scalar()?;
+        Ok(self.node.value().trim())
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de, '_> {
+    type Error = Error;
+
+    /// https://serde.rs/impl-deserialize.html
+    /// The various other deserialize_* methods. Non-self-describing formats like Postcard need
+    /// to be told what is in the input in order to deserialize it.
+    /// The deserialize_* methods are hints to the deserializer for how to interpret the next
+    /// piece of input. Non-self-describing formats are not able to deserialize something like serde_json::Value
+    /// which relies on Deserializer::deserialize_any.
+    ///
+    ///
+    /// support:
+    /// 1. array: 1,2,3
+    /// 2. bool: true or false or True or False, unless
+    ///    [`infer_bool`](crate::EnvDeserializer::infer_bool) is disabled
+    /// 3. number: must be valid u64 (with optional `+`) or i64, or f64 like
+    ///    `1.5`, `inf` and `nan` if
+    ///    [`infer_float`](crate::EnvDeserializer::infer_float) is enabled
+    /// 4. string: "hello", quotes around value like `"a,b"` are stripped and
+    ///    the value is never a sequence
+    /// 5. Enums with unit variants see <https://github.com/Xuanwo/serde-env/pull/16>
+    /// 6. map: node without value but with nested keys like `D_AA=1`,
+    ///    every nested level becomes a map, so untyped targets like
+    ///    `serde_json::Value` mirror the tree
+    /// 7. empty: unit (`null` in `serde_json::Value`), or empty string if
+    ///    [`empty_string_is_none`](crate::EnvDeserializer::empty_string_is_none)
+    ///    is disabled and the key is supplied. `Option` is not affected since
+    ///    it's handled by `deserialize_option`.
+    fn deserialize_any<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Untyped values tell nothing about the type of other map entries.
+        self.entry = None;
+        self.lenient = false;
+        // dbg!(&self.node.value());
+        if self.node.value().is_empty() && self.node.has_children() {
+            let keys = self.node.sorted_keys();
+            return vis.visit_map(MapAccessor::new(keys, self.node, self.ctx, self.path));
+        }
+
+        let bytes = self.node.value().as_bytes();
+        if bytes.is_empty() {
+            if !self.ctx.opts.empty_string_is_none && self.node.is_present_empty() {
+                return self.deserialize_str(vis);
+            }
+            return vis.visit_unit();
+        }
+        let first = bytes[0];
+
+        match first {
+            b'"' | b'\'' if self.unquoted().is_some() => {
+                return self.deserialize_str(vis);
+            }
+            _ if self
+                .ctx
+                .opts
+                .seq_inference
+                .infer(self.node.value(), self.ctx.opts) =>
+            {
+                return self.deserialize_seq(vis);
+            }
+            b'0'..=b'9' | b'+' if is_digits(&bytes[1..]) => {
+                // Values above 64 bits are strings unless `infer_int128`,
+                // and always under flatten since serde can't buffer 128-bit
+                // integers.
+                let int128 = self.ctx.opts.infer_int128 && !self.ctx.flatten;
+                let v = numeric(self.node.value());
+                return match v.parse::<u64>() {
+                    Ok(v) => vis.visit_u64(v),
+                    Err(_) if int128 => match v.parse::<u128>() {
+                        Ok(v) => vis.visit_u128(v),
+                        Err(_) => self.deserialize_str(vis),
+                    },
+                    Err(_) => self.deserialize_str(vis),
+                };
+            }
+            b'-' if is_digits(&bytes[1..]) => {
+                let int128 = self.ctx.opts.infer_int128 && !self.ctx.flatten;
+                let v = numeric(self.node.value());
+                return match v.parse::<i64>() {
+                    Ok(v) => vis.visit_i64(v),
+                    Err(_) if int128 => match v.parse::<i128>() {
+                        Ok(v) => vis.visit_i128(v),
+                        Err(_) => self.deserialize_str(vis),
+                    },
+                    Err(_) => self.deserialize_str(vis),
+                };
+            }
+            b't' | b'f' | b'T' | b'F' if self.ctx.opts.infer_bool => {
+                if bytes.eq_ignore_ascii_case(b"true") {
+                    return vis.visit_bool(true);
+                } else if bytes.eq_ignore_ascii_case(b"false") {
+                    return vis.visit_bool(false);
+                }
+            }
+            _ => {}
+        };
+        if self.ctx.opts.infer_float || self.ctx.flatten {
+            let v = numeric(self.node.value());
+            if self.ctx.opts.infer_float || is_decimal(&v) {
+                if let Ok(v) = v.parse::<f64>() {
+                    return vis.visit_f64(v);
+                }
+            }
+        }
+        self.deserialize_str(vis)
+    }
+
+    fn deserialize_bool<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_bool(self.0.value().parse().map_err(Error::new)?)
+        vis.visit_bool(self.scalar()?.parse().map_err(Error::new)?)
     }
 
-    fn deserialize_i8<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i8<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_i8(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_i8(v))
     }
 
-    fn deserialize_i16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i16<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_i16(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_i16(v))
     }
 
-    fn deserialize_i32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i32<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_i32(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_i32(v))
     }
 
-    fn deserialize_i64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i64<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_i64(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_i64(v))
     }
 
-    fn deserialize_u8<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u8<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_u8(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_u8(v))
     }
 
-    fn deserialize_u16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u16<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_u16(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_u16(v))
     }
 
     forward_to_deserialize_any! {
         unit unit_struct
-        tuple_struct ignored_any
     }
 
-    fn deserialize_u32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    /// Ignored values like unknown fields are skipped without parsing.
+    fn deserialize_ignored_any<V>(self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_u32(self.0.value().parse().map_err(Error::new)?)
+        vis.visit_unit()
     }
 
-    fn deserialize_u64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u32<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_u64(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_u32(v))
     }
 
-    fn deserialize_f32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u64<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_f32(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_u64(v))
     }
 
-    fn deserialize_f64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i128<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_f64(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_i128(v))
     }
 
-    fn deserialize_char<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u128<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_char(self.0.value().parse().map_err(Error::new)?)
+        self.visit_int(|v| vis.visit_u128(v))
     }
 
-    fn deserialize_str<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_f32<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-
-        vis.visit_str(self.0.value())
+        vis.visit_f32(numeric(self.scalar()?).parse().map_err(Error::new)?)
     }
 
-    fn deserialize_string<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    /// Besides decimals, `inf`, `-inf`, `infinity` and `nan` are accepted
+    /// case-insensitively, as [`f64::from_str`](std::str::FromStr) does.
+    /// Underscores like `3_000.5` are accepted too.
+    ///
+    /// Untyped targets only see them as floats if
+    /// [`infer_float`](crate::EnvDeserializer::infer_float) is enabled, so a
+    /// value like `nan` doesn't surprisingly turn into a float.
+    fn deserialize_f64<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_string(self.0.into_value())
+        vis.visit_f64(numeric(self.scalar()?).parse().map_err(Error::new)?)
     }
 
-    fn deserialize_bytes<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    fn deserialize_char<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_bytes(self.0.value().as_bytes())
+        self.check_scalar()?;
+        let v = self.node.value();
+        let mut chars = v.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => vis.visit_char(c),
+            _ => Err(Error::new(InvalidChar(v.to_string()))),
+        }
     }
 
-    fn deserialize_byte_buf<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    /// Quotes around value like `"Hello, world"` are stripped.
+    fn deserialize_str<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_byte_buf(self.0.into_value().into_bytes())
+        self.check_scalar()?;
+        match self.node {
+            Cow::Borrowed(node) => {
+                vis.visit_borrowed_str(unquote(node.value()).unwrap_or(node.value()))
+            }
+            Cow::Owned(node) => vis.visit_str(unquote(node.value()).unwrap_or(node.value())),
+        }
     }
 
-    fn deserialize_option<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    /// Quotes around value like `"Hello, world"` are stripped.
+    fn deserialize_string<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        if self.0.is_empty() {
-            vis.visit_none()
-        } else {
-            vis.visit_some(Deserializer(self.0))
+        self.check_scalar()?;
+        match self.node {
+            Cow::Borrowed(node) => {
+                vis.visit_borrowed_str(unquote(node.value()).unwrap_or(node.value()))
+            }
+            Cow::Owned(node) => match unquote(node.value()) {
+                Some(v) => vis.visit_str(v),
+                None => vis.visit_string(node.into_value()),
+            },
         }
     }
 
-    fn deserialize_newtype_struct<V>(
-        self,
+    fn deserialize_bytes<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_scalar()?;
+        if let Some(v) = self.ctx.opts.byte_encoding.decode(self.node.value())? {
+            return vis.visit_byte_buf(v);
+        }
+        match self.node {
+            Cow::Borrowed(node) => vis.visit_borrowed_bytes(node.value_bytes()),
+            Cow::Owned(node) => vis.visit_bytes(node.value_bytes()),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.check_scalar()?;
+        if let Some(v) = self.ctx.opts.byte_encoding.decode(self.node.value())? {
+            return vis.visit_byte_buf(v);
+        }
+        match self.node {
+            Cow::Borrowed(node) => vis.visit_borrowed_bytes(node.value_bytes()),
+            Cow::Owned(node) => vis.visit_byte_buf(node.into_value_bytes()),
+        }
+    }
+
+    fn deserialize_option<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // A nested struct with only empty values is `None` too, since empty
+        // values are the same as unset ones.
+        let none = if self.ctx.opts.empty_string_is_none {
+            self.node.is_empty_tree()
+        } else {
+            self.node.is_absent()
+        };
+
+        if none {
+            vis.visit_none()
+        } else {
+            vis.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
         _name: &'static str,
         vis: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        vis.visit_newtype_struct(Deserializer(self.0))
+        vis.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    /// Sequences can be read from comma separated value or from indexed
+    /// children.
+    ///
+    /// - `TAGS=a,b` => `["a", "b"]`
+    /// - `TAGS_1=a`, `TAGS_2=b` => `["a", "b"]`
+    /// - `ENTRIES_0_A=1`, `ENTRIES_0_B=2`, `ENTRIES_1_A=3` => `[{"a": "1", "b": "2"}, {"a": "3"}]`
+    ///
+    /// Indexed children are ordered by their index, and take precedence
+    /// over the comma separated value.
+    ///
+    /// A quoted value like `TAGS="a,b"` is never split => `["a,b"]`.
+    ///
+    /// If [`group_delimiter`](crate::EnvDeserializer::group_delimiter) is
+    /// set and found in value, value is split into groups first, and every
+    /// group is a sequence itself:
+    ///
+    /// - `MATRIX=1,2;3,4` => `[[1, 2], [3, 4]]`
+    ///
+    /// If [`json_seq`](crate::EnvDeserializer::json_seq) is enabled, a value
+    /// starting with `[` is a JSON array, so elements can be structs:
+    ///
+    /// - `SERVERS=[{"host":"a"},{"host":"b"}]` => `[{"host": "a"}, {"host": "b"}]`
+    fn deserialize_seq<V>(mut self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let elements = self
-            .0
-            .value()
-            .split(',')
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty())
-            .collect();
+        if self.node.has_indexed_children() {
+            self.mark_subtree();
+            let (ctx, path) = (self.ctx, self.path.clone());
+            let elements = self
+                .into_indexed_children()
+                .into_iter()
+                .map(|(_, node)| node)
+                .collect();
+
+            return vis.visit_seq(SeqAccessor::new(elements, ctx, path));
+        }
+        self.leaf();
+
+        #[cfg(feature = "json")]
+        if self.ctx.opts.json_seq && self.node.value().trim_start().starts_with('[') {
+            let v: serde_json::Value = crate::json::parse(self.node.value())?;
+            return de::Deserializer::deserialize_seq(v, vis).map_err(Error::new);
+        }
+
+        if let Some(v) = self.unquoted() {
+            let elements = vec![owned(v)];
+            return vis.visit_seq(SeqAccessor::new(elements, self.ctx, self.path));
+        }
+
+        if let Some(groups) = self.split_groups() {
+            let elements = groups.into_iter().map(owned).collect();
+            return vis.visit_seq(SeqAccessor::new(elements, self.ctx, self.path));
+        }
+
+        // Trailing empty elements like `1,2,` are dropped, while interior
+        // ones like `1,,3` are kept for `Option` elements.
+        let mut elements: Vec<_> = self.split().collect();
+        while elements.last().is_some_and(|v| v.is_empty()) {
+            elements.pop();
+        }
+        let elements = elements.into_iter().map(owned).collect();
 
-        vis.visit_seq(SeqAccessor::new(elements))
+        vis.visit_seq(SeqAccessor::new(elements, self.ctx, self.path))
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, vis: V) -> Result<V::Value, Self::Error>
+    /// Tuples (including fixed-size arrays like `[T; N]`) can be read from
+    /// comma separated value or from indexed children.
+    ///
+    /// - `RGB=255,128,0` => `[255, 128, 0]`
+    /// - `M_0_0=1`, `M_0_1=2`, `M_1_0=3`, `M_1_1=4` => `[[1, 2], [3, 4]]`
+    ///
+    /// Indexed children must cover `0..len` exactly, which is checked for
+    /// every dimension. Comma separated values must have `len` elements too.
+    fn deserialize_tuple<V>(mut self, len: usize, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let elements = self
-            .0
-            .value()
-            .split(',')
-            .map(|v| v.trim().to_string())
-            .collect();
+        if self.node.has_indexed_children() {
+            self.mark_subtree();
+            let (ctx, path) = (self.ctx, self.path.clone());
+            let children = self.into_indexed_children();
+            if children.len() != len {
+                return Err(de::Error::custom(format!(
+                    "invalid length {}, expected {} indexed elements",
+                    children.len(),
+                    len
+                )));
+            }
+
+            let mut elements = Vec::with_capacity(len);
+            for (expected, (idx, node)) in children.into_iter().enumerate() {
+                if idx != expected {
+                    return Err(de::Error::custom(format!(
+                        "missing indexed element {expected}"
+                    )));
+                }
+                elements.push(node);
+            }
+
+            return vis.visit_seq(SeqAccessor::new(elements, ctx, path));
+        }
+        self.leaf();
+
+        let elements: Vec<_> = self.split().map(owned).collect();
+        if elements.len() != len {
+            return Err(de::Error::custom(format!(
+                "invalid length {}, expected {} elements separated by `{}`",
+                elements.len(),
+                len,
+                self.ctx.opts.list_delimiter
+            )));
+        }
+
+        vis.visit_seq(SeqAccessor::new(elements, self.ctx, self.path))
+    }
 
-        vis.visit_seq(SeqAccessor::new(elements))
+    /// Tuple structs like `struct Pair(u32, u32)` are read like tuples.
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, vis)
     }
 
     fn deserialize_map<V>(self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let keys = self.0.flatten("");
-        vis.visit_map(MapAccessor::new(keys, self.0))
+        self.check_collision("a map")?;
+        self.mark_subtree();
+        let (ctx, path) = (self.ctx, self.path.clone());
+        // Both nested keys like `db` and flat keys like `db_host` are
+        // visited, so untyped values like `serde_json::Value` see `db` as an
+        // object, while flattened structs can still find `db_host`.
+        //
+        // Flat keys are skipped if their nested key is read as a struct,
+        // map or indexed sequence, so `DB_PRIMARY_HOST` only fills entry
+        // `primary` of `HashMap<String, DbConfig>`. The same goes for fields
+        // of a struct with `#[serde(flatten)]`, so with field `db: DbConfig`
+        // the flattened map doesn't get `db_host` again.
+        //
+        // Nested keys without value like `app` of `APP_NAME` never become
+        // empty entries of `HashMap<String, String>`, see
+        // `MapAccessor::next_entry_seed`.
+        let keys = self.node.flatten();
+        vis.visit_map(MapAccessor::new(keys, self.node, self.ctx, self.path))
+            .map_err(|err| ctx.locate(err, &path))
     }
 
+    /// Nested keys not matched by any field are visited too, so
+    /// `#[serde(deny_unknown_fields)]` works. Avoid it on the top level
+    /// struct of `from_env`, since every other env var is unknown there.
+    ///
+    /// Fields whose subtree is absent are never visited, so a
+    /// `#[serde(default)]` nested struct falls back to its `Default`.
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
@@ -392,9 +1418,13 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     where
         V: Visitor<'de>,
     {
-        let keys = fields.iter().map(|v| v.to_string()).collect();
-
-        vis.visit_map(MapAccessor::new(keys, self.0))
+        self.check_collision("a struct")?;
+        self.mark_subtree();
+        let (ctx, path) = (self.ctx, self.path.clone());
+        vis.visit_map(MapAccessor::new_struct(
+            fields, self.node, self.ctx, self.path,
+        ))
+        .map_err(|err| ctx.locate(err, &path))
     }
 
     fn deserialize_enum<V>(
@@ -408,7 +1438,7 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     {
         let keys = variants.iter().map(|v| v.to_string()).collect();
 
-        vis.visit_enum(EnumAccessor::new(keys, self.0))
+        vis.visit_enum(EnumAccessor::new(keys, self.node, self.ctx, self.path))
     }
 
     fn deserialize_identifier<V>(self, vis: V) -> Result<V::Value, Self::Error>
@@ -419,19 +1449,197 @@ impl<'de> de::Deserializer<'de> for Deserializer {
     }
 }
 
-struct SeqAccessor {
-    elements: std::vec::IntoIter<String>,
+/// Integers that can be parsed with a radix.
+trait Int: FromStr<Err = ParseIntError> + Default + PartialEq {
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(
+            impl Int for $t {
+                fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
+                    <$t>::from_str_radix(src, radix)
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+/// Value of a char field that is not a single character.
+#[derive(Debug)]
+struct InvalidChar(String);
+
+impl std::fmt::Display for InvalidChar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected a single character, got {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidChar {}
+
+/// Value of `0` for a `NonZero*` field.
+#[derive(Debug)]
+struct NonZero;
+
+impl std::fmt::Display for NonZero {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("expected a non-zero value")
+    }
+}
+
+impl std::error::Error for NonZero {}
+
+/// Value of an enum that matches no variant.
+#[derive(Debug)]
+struct UnknownVariant {
+    value: String,
+    variants: Vec<String>,
+}
+
+impl std::fmt::Display for UnknownVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no variant `{}` found, expected one of: ", self.value)?;
+        for (idx, v) in self.variants.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(v)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownVariant {}
+
+/// Both value and nested keys found while collisions are detected.
+#[derive(Debug)]
+struct Collision {
+    value: String,
+    expected: &'static str,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "both value `{}` and nested keys found, but expected {}",
+            self.value, self.expected
+        )
+    }
+}
+
+impl std::error::Error for Collision {}
+
+/// Nested keys found while a scalar is expected.
+///
+/// Keys are relative to the field, and get prefixed by the field name in
+/// [`Error::with_field`].
+#[derive(Debug)]
+pub(crate) struct NestedKeys(pub(crate) Vec<String>);
+
+impl std::fmt::Display for NestedKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("expected a scalar but found nested keys ")?;
+        for (idx, key) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(&key.to_uppercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NestedKeys {}
+
+/// Check if all bytes are digits or underscores.
+fn is_digits(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| b.is_ascii_digit() || b == b'_')
+}
+
+/// Check if value is a decimal like `1.5` or `-2e3`, unlike `nan` or `inf`.
+fn is_decimal(v: &str) -> bool {
+    v.bytes().any(|b| b.is_ascii_digit())
+        && v.bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.' | b'e' | b'E'))
+}
+
+/// Strip underscores in a number like `1_000_000`, as Rust literals do.
+///
+/// Values starting with `_` are kept as is, so they fail to parse.
+fn numeric(v: &str) -> Cow<'_, str> {
+    let unsigned = v.strip_prefix(['-', '+']).unwrap_or(v);
+    if v.contains('_') && !unsigned.starts_with('_') {
+        Cow::Owned(v.replace('_', ""))
+    } else {
+        Cow::Borrowed(v)
+    }
+}
+
+/// Parse an integer, with `0x`, `0o` and `0b` prefixes for hex, octal and
+/// binary like `0xFF` and `-0b1010`, or as decimal otherwise.
+///
+/// Underscores like `1_000` and `0xFF_FF` are accepted.
+fn parse_int<T: Int>(v: &str) -> Result<T, ParseIntError> {
+    let v = &*numeric(v);
+    let (neg, unsigned) = match v.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, v.strip_prefix('+').unwrap_or(v)),
+    };
+    let radix = match unsigned.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => return v.parse(),
+    };
+
+    let digits = &unsigned[2..];
+    if digits.starts_with(['+', '-']) {
+        // Let it fail like `0x-1`.
+        return v.parse();
+    }
+    if neg {
+        T::from_str_radix(&format!("-{digits}"), radix)
+    } else {
+        T::from_str_radix(digits, radix)
+    }
+}
+
+/// Strip matching `"` or `'` quotes around value.
+fn unquote(v: &str) -> Option<&str> {
+    let first = v.chars().next()?;
+    if v.len() >= 2 && matches!(first, '"' | '\'') && v.ends_with(first) {
+        Some(&v[1..v.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Create an owned node for sequence elements.
+fn owned<'de>(v: &str) -> Cow<'de, Node> {
+    Cow::Owned(Node::new(v))
+}
+
+struct SeqAccessor<'de, 'a> {
+    elements: std::iter::Enumerate<std::vec::IntoIter<Cow<'de, Node>>>,
+    ctx: Context<'a>,
+    path: String,
 }
 
-impl SeqAccessor {
-    fn new(keys: Vec<String>) -> Self {
+impl<'de, 'a> SeqAccessor<'de, 'a> {
+    fn new(elements: Vec<Cow<'de, Node>>, ctx: Context<'a>, path: String) -> Self {
         Self {
-            elements: keys.into_iter(),
+            elements: elements.into_iter().enumerate(),
+            ctx,
+            path,
         }
     }
 }
 
-impl<'de> SeqAccess<'de> for SeqAccessor {
+impl<'de> SeqAccess<'de> for SeqAccessor<'de, '_> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
@@ -440,28 +1648,205 @@ impl<'de> SeqAccess<'de> for SeqAccessor {
     {
         match self.elements.next() {
             None => Ok(None),
-            Some(v) => Ok(Some(seed.deserialize(Deserializer(Node::new(v)))?)),
+            Some((idx, v)) => {
+                let path = self.ctx.join(&self.path, &idx.to_string());
+                Ok(Some(
+                    seed.deserialize(Deserializer::with_path(v, self.ctx, path))?,
+                ))
+            }
         }
     }
 }
 
-struct MapAccessor {
-    last_value: Option<Node>,
-    keys: std::collections::hash_set::IntoIter<String>,
-    node: Node,
+/// Normalize a requested key into the key of node, which is lowercased.
+///
+/// Field names renamed by `#[serde(rename_all = "SCREAMING_SNAKE_CASE")]`
+/// and friends are matched case-insensitively in this way. Since env keys
+/// can't contain `-`, it's mapped to `_`, so `log-level` reads `LOG_LEVEL`.
+///
+/// As a result, fields like `log_level` and `log-level` in one struct both
+/// read `LOG_LEVEL`.
+pub(crate) fn env_key(key: &str) -> String {
+    key.to_lowercase().replace('-', "_")
+}
+
+struct MapAccessor<'de, 'a> {
+    /// Env key, full key and value of the last entry, value is `None` for
+    /// placeholders.
+    last_value: Option<(String, String, Option<Cow<'de, Node>>)>,
+    /// Remaining keys along with their env keys.
+    keys: std::vec::IntoIter<(String, String)>,
+    node: Cow<'de, Node>,
+    ctx: Context<'a>,
+    path: String,
+    /// Record fields into report, only enabled for structs.
+    record: bool,
+    /// Children not matched by any struct field, visited after all fields
+    /// so that `#[serde(deny_unknown_fields)]` can reject them.
+    unknown: std::vec::IntoIter<String>,
+    /// Whether keys are read as identifiers, which means entries are
+    /// fields of a struct with `#[serde(flatten)]` instead of a real map.
+    identifier: Cell<bool>,
+    /// How the value of the last map entry is read.
+    read: Cell<EntryRead>,
+    /// Env keys of map entries read as subtrees, whose flat keys like
+    /// `primary_host` are skipped after `primary`.
+    subtrees: Vec<String>,
 }
 
-impl MapAccessor {
-    fn new(keys: HashSet<String>, node: Node) -> Self {
+impl<'de, 'a> MapAccessor<'de, 'a> {
+    fn new(
+        keys: impl IntoIterator<Item = String>,
+        node: Cow<'de, Node>,
+        ctx: Context<'a>,
+        path: String,
+    ) -> Self {
         Self {
             last_value: None,
-            keys: keys.into_iter(),
+            keys: keys
+                .into_iter()
+                .map(|k| {
+                    let env_key = env_key(&k);
+                    (k, env_key)
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
             node,
+            ctx,
+            path,
+            record: false,
+            unknown: Vec::new().into_iter(),
+            identifier: Cell::new(false),
+            read: Cell::new(EntryRead::Unknown),
+            subtrees: Vec::new(),
+        }
+    }
+
+    fn new_struct(
+        fields: &'static [&'static str],
+        node: Cow<'de, Node>,
+        ctx: Context<'a>,
+        path: String,
+    ) -> Self {
+        let known: Vec<_> = fields.iter().map(|v| env_key(v)).collect();
+        let mut unknown: Vec<_> = node
+            .childrens()
+            .into_iter()
+            .filter(|k| {
+                // Child `db` is known by field `db_url`.
+                !known.iter().any(|v| {
+                    v.strip_prefix(k.as_str())
+                        .is_some_and(|v| v.is_empty() || v.starts_with('_'))
+                })
+            })
+            .collect();
+        unknown.sort();
+
+        let keys = fields.iter().map(|v| v.to_string());
+        Self {
+            record: true,
+            unknown: unknown.into_iter(),
+            ..Self::new(keys, node, ctx, path)
+        }
+    }
+
+    /// Take the child with full key name out of node.
+    ///
+    /// Owned children are moved out instead of cloned, unless they overlap
+    /// with remaining keys like `db` and `db_url`, which still need them.
+    /// Borrowed children are never cloned.
+    fn take(&mut self, k: &str) -> Option<Cow<'de, Node>> {
+        let overlaps = |a: &str, b: &str| a.strip_prefix(b).is_some_and(|v| v.starts_with('_'));
+
+        match &mut self.node {
+            Cow::Borrowed(node) => node.get(k).map(Cow::Borrowed),
+            Cow::Owned(node) => {
+                let shared = self
+                    .keys
+                    .as_slice()
+                    .iter()
+                    .any(|(_, v)| v == k || overlaps(v, k) || overlaps(k, v));
+                if shared {
+                    node.get(k).cloned().map(Cow::Owned)
+                } else {
+                    node.take(k).map(Cow::Owned)
+                }
+            }
+        }
+    }
+
+    /// Whether the key is a flat key under an entry read as a subtree.
+    fn under_subtree(&self, env_key: &str) -> bool {
+        self.subtrees.iter().any(|v| {
+            env_key
+                .strip_prefix(v.as_str())
+                .is_some_and(|v| v.starts_with('_'))
+        })
+    }
+
+    fn key_deserializer(&self, key: String) -> KeyDeserializer<'_> {
+        KeyDeserializer {
+            key,
+            identifier: &self.identifier,
         }
     }
+
+    /// Visit the next child not matched by any struct field.
+    fn next_unknown_key<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        for key in self.unknown.by_ref() {
+            let value = match &mut self.node {
+                Cow::Borrowed(node) => node.get(&key).map(Cow::Borrowed),
+                Cow::Owned(node) => node.take(&key).map(Cow::Owned),
+            };
+            match value {
+                None => {}
+                Some(v) if self.ctx.opts.empty_string_is_none && v.is_empty() => {}
+                Some(v) => {
+                    let path = self.ctx.join(&self.path, &key);
+                    self.last_value = Some((key.clone(), path, Some(v)));
+                    return Ok(Some(seed.deserialize(key.into_deserializer())?));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Path in `KEY_FILE` to read the struct field from, if enabled.
+    ///
+    /// Returns `None` if the field has a value, so `KEY` always wins.
+    fn file_path(&self, k: &str) -> Option<&str> {
+        if !self.record || !self.ctx.opts.read_file_keys {
+            return None;
+        }
+        if self.node.get(k).is_some_and(|v| !v.value().is_empty()) {
+            return None;
+        }
+        self.node
+            .get(&format!("{k}_file"))
+            .map(Node::value)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Read the struct field from the file at `KEY_FILE` if enabled.
+    fn read_file(&self, k: &str) -> Result<Option<Cow<'de, Node>>, Error> {
+        let Some(path) = self.file_path(k) else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            Error::io(
+                Some(format!("{}_FILE", k.to_uppercase())),
+                path.to_string(),
+                err,
+            )
+        })?;
+        Ok(Some(owned(content.trim())))
+    }
 }
 
-impl<'de> de::MapAccess<'de> for MapAccessor {
+impl<'de> de::MapAccess<'de> for MapAccessor<'de, '_> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
@@ -474,19 +1859,48 @@ impl<'de> de::MapAccess<'de> for MapAccessor {
         );
 
         loop {
-            let key = match self.keys.next() {
-                None => return Ok(None),
+            let (key, env_key) = match self.keys.next() {
+                None => return self.next_unknown_key(seed),
                 Some(v) => v,
             };
+            if self.under_subtree(&env_key) {
+                continue;
+            }
+            let path = self.ctx.join(&self.path, &env_key);
 
-            match self.node.get(&key) {
+            // Files are read by the first pass only, see
+            // `EnvDeserializer::try_from_env`.
+            if self.ctx.finding_missing() && self.file_path(&env_key).is_some() {
+                self.last_value = Some((env_key, path, None));
+                return Ok(Some(seed.deserialize(self.key_deserializer(key))?));
+            }
+            let value = match self.read_file(&env_key)? {
+                Some(v) => Some(v),
+                None => self.take(&env_key),
+            };
+            match value {
                 // If key is not found inside node, skip it and continue.
-                None => continue,
+                // Empty value is treated as not found too if configured.
+                None => {}
+                Some(v) if self.ctx.opts.empty_string_is_none && v.is_empty() => {}
                 Some(v) => {
-                    self.last_value = Some(v.clone());
-                    return Ok(Some(seed.deserialize(key.into_deserializer())?));
+                    if self.record && !v.has_children() {
+                        self.ctx.record_env(&path, v.value());
+                    }
+                    self.last_value = Some((env_key, path, Some(v)));
+                    return Ok(Some(seed.deserialize(self.key_deserializer(key))?));
                 }
             }
+
+            // Known missing field, fill it with a placeholder.
+            if self.ctx.is_missing(&path) {
+                self.last_value = Some((env_key, path, None));
+                return Ok(Some(seed.deserialize(self.key_deserializer(key))?));
+            }
+
+            if self.record {
+                self.ctx.record_default(&path);
+            }
         }
     }
 
@@ -494,475 +1908,3636 @@ impl<'de> de::MapAccess<'de> for MapAccessor {
     where
         V: DeserializeSeed<'de>,
     {
-        let value = self
+        let (key, path, value) = self
             .last_value
             .take()
             .expect("value for current entry is missing");
 
-        seed.deserialize(Deserializer(value))
-    }
-}
-
-struct EnumAccessor {
-    keys: std::vec::IntoIter<String>,
-    node: Node,
-}
-
-impl EnumAccessor {
-    fn new(keys: Vec<String>, node: Node) -> Self {
-        Self {
-            keys: keys.into_iter(),
-            node,
+        match value {
+            // Fields of a struct with `#[serde(flatten)]` are tracked too, so
+            // flat keys under a nested field don't end up in a flattened map.
+            Some(value) if !self.record => {
+                let ctx = if self.identifier.get() {
+                    self.ctx.flattened()
+                } else {
+                    self.ctx
+                };
+                let mut de = Deserializer::with_path(value, ctx, path);
+                de.entry = Some(&self.read);
+                de.lenient = !self.identifier.get();
+                let v = seed.deserialize(de);
+                if self.read.take() == EntryRead::Subtree {
+                    self.subtrees.push(key.clone());
+                }
+                v
+            }
+            Some(value) => seed.deserialize(Deserializer::with_path(value, self.ctx, path)),
+            None => seed.deserialize(Missing::new(self.ctx, path)),
         }
+        .map_err(|err| err.with_field(&key))
     }
-}
-
-impl<'de> de::EnumAccess<'de> for EnumAccessor {
-    type Error = Error;
-    type Variant = VariantAccessor;
 
-    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    /// Entries of real maps are read value first, so that a nested key
+    /// without value like `app` of `APP_NAME` becomes entry `app_name` if its
+    /// value is read as a scalar, see [`Deserializer::leaf`].
+    fn next_entry_seed<K, V>(
+        &mut self,
+        kseed: K,
+        vseed: V,
+    ) -> Result<Option<(K::Value, V::Value)>, Self::Error>
     where
+        K: DeserializeSeed<'de>,
         V: DeserializeSeed<'de>,
     {
-        let key = self
-            .keys
-            .find(|key| self.node.value() == key)
-            .ok_or_else(|| {
-                de::Error::custom(format!("no variant `{}` found", self.node.value()))
-            })?;
+        if self.record {
+            return match self.next_key_seed(kseed)? {
+                Some(k) => Ok(Some((k, self.next_value_seed(vseed)?))),
+                None => Ok(None),
+            };
+        }
 
-        let variant = VariantAccessor::new(self.node);
-        Ok((seed.deserialize(key.into_deserializer())?, variant))
+        while let Some((key, env_key)) = self.keys.next() {
+            if self.under_subtree(&env_key) {
+                continue;
+            }
+            let value = match self.take(&env_key) {
+                None => continue,
+                Some(v) if self.ctx.opts.empty_string_is_none && v.is_empty_tree() => continue,
+                Some(v) => v,
+            };
+
+            let path = self.ctx.join(&self.path, &env_key);
+            let mut de = Deserializer::with_path(value, self.ctx, path);
+            de.entry = Some(&self.read);
+            de.lenient = true;
+            let value = vseed.deserialize(de);
+            let (key, env_key) = match self.read.take() {
+                EntryRead::Unknown => (key, env_key),
+                EntryRead::Subtree => {
+                    self.subtrees.push(env_key.clone());
+                    (key, env_key)
+                }
+                EntryRead::Leaf(leaf) => {
+                    let env_key = format!("{env_key}_{leaf}");
+                    // Skip the nested keys down to the leaf, which are next.
+                    while self.keys.as_slice().first().is_some_and(|(_, v)| {
+                        env_key
+                            .strip_prefix(v.as_str())
+                            .is_some_and(|v| v.is_empty() || v.starts_with('_'))
+                    }) {
+                        self.keys.next();
+                    }
+                    (format!("{key}_{leaf}"), env_key)
+                }
+            };
+            let value = value.map_err(|err| err.with_field(&env_key))?;
+            let key = kseed.deserialize(self.key_deserializer(key))?;
+            return Ok(Some((key, value)));
+        }
+        Ok(None)
     }
 }
 
-struct VariantAccessor {
-    node: Node,
+/// Deserializer of map keys, which notes whether keys are read as
+/// identifiers.
+///
+/// Integer keys like `2` in `NAMES_2` are parsed for maps like
+/// `BTreeMap<u32, String>`.
+struct KeyDeserializer<'a> {
+    key: String,
+    identifier: &'a Cell<bool>,
 }
 
-impl VariantAccessor {
-    fn new(node: Node) -> Self {
-        Self { node }
+impl KeyDeserializer<'_> {
+    fn int<T: Int>(&self) -> Result<T, Error> {
+        parse_int(&self.key).map_err(Error::new)
     }
 }
 
-impl<'de> de::VariantAccess<'de> for VariantAccessor {
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'_> {
     type Error = Error;
 
-    fn unit_variant(self) -> Result<(), Self::Error> {
-        if self.node.has_children() {
-            return Err(de::Error::custom("variant is not unit"));
-        }
-        Ok(())
-    }
-    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    fn deserialize_any<V>(self, vis: V) -> Result<V::Value, Self::Error>
     where
-        T: DeserializeSeed<'de>,
+        V: Visitor<'de>,
     {
-        seed.deserialize(Deserializer(self.node))
+        IntoDeserializer::<Error>::into_deserializer(self.key).deserialize_any(vis)
     }
-    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+
+    fn deserialize_identifier<V>(self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        Err(de::Error::custom("tuple variant is not supported"))
+        self.identifier.set(true);
+        self.deserialize_any(vis)
     }
-    fn struct_variant<V>(
-        self,
-        fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
+
+    fn deserialize_i8<V>(self, vis: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let keys = fields.iter().map(|v| v.to_string()).collect();
-
-        visitor.visit_map(MapAccessor::new(keys, self.node))
+        vis.visit_i8(self.int()?)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+    fn deserialize_i16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_i16(self.int()?)
+    }
 
-    use serde::Deserialize;
+    fn deserialize_i32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_i32(self.int()?)
+    }
+
+    fn deserialize_i64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_i64(self.int()?)
+    }
+
+    fn deserialize_u8<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u8(self.int()?)
+    }
+
+    fn deserialize_u16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u16(self.int()?)
+    }
+
+    fn deserialize_u32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u32(self.int()?)
+    }
+
+    fn deserialize_u64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u64(self.int()?)
+    }
+
+    fn deserialize_i128<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_i128(self.int()?)
+    }
+
+    fn deserialize_u128<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u128(self.int()?)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        IntoDeserializer::<Error>::into_deserializer(self.key).deserialize_enum(name, variants, vis)
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct EnumAccessor<'de, 'a> {
+    keys: Vec<String>,
+    node: Cow<'de, Node>,
+    ctx: Context<'a>,
+    path: String,
+}
+
+impl<'de, 'a> EnumAccessor<'de, 'a> {
+    fn new(keys: Vec<String>, node: Cow<'de, Node>, ctx: Context<'a>, path: String) -> Self {
+        Self {
+            keys,
+            node,
+            ctx,
+            path,
+        }
+    }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccessor<'de, 'a> {
+    type Error = Error;
+    type Variant = VariantAccessor<'de, 'a>;
+
+    /// Variants are matched exactly, or case-insensitively if
+    /// [`enum_case_insensitive`](crate::EnvDeserializer::enum_case_insensitive)
+    /// is enabled and no variant matches exactly.
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self.node.value();
+        let idx = self
+            .keys
+            .iter()
+            .position(|key| value == key)
+            .or_else(|| {
+                if !self.ctx.opts.enum_case_insensitive {
+                    return None;
+                }
+                self.keys
+                    .iter()
+                    .position(|key| value.eq_ignore_ascii_case(key))
+            })
+            .ok_or_else(|| {
+                Error::new(UnknownVariant {
+                    value: value.to_string(),
+                    variants: self.keys.clone(),
+                })
+            })?;
+        let key = self.keys.swap_remove(idx);
+
+        let variant = VariantAccessor::new(self.node, self.ctx, self.path);
+        Ok((seed.deserialize(key.into_deserializer())?, variant))
+    }
+}
+
+struct VariantAccessor<'de, 'a> {
+    node: Cow<'de, Node>,
+    ctx: Context<'a>,
+    path: String,
+}
+
+impl<'de, 'a> VariantAccessor<'de, 'a> {
+    fn new(node: Cow<'de, Node>, ctx: Context<'a>, path: String) -> Self {
+        Self { node, ctx, path }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccessor<'de, '_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        if self.node.has_children() {
+            return Err(de::Error::custom("variant is not unit"));
+        }
+        Ok(())
+    }
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(Deserializer::with_path(self.node, self.ctx, self.path))
+    }
+    /// Tuple variants are read from indexed children, like `E=Pair`,
+    /// `E_0=1` and `E_1=2`.
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.node.has_indexed_children() {
+            return Err(de::Error::custom(
+                "tuple variant expects indexed elements like `_0`",
+            ));
+        }
+        let de = Deserializer::with_path(self.node, self.ctx, self.path);
+        de::Deserializer::deserialize_tuple(de, len, visitor)
+    }
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapAccessor::new_struct(
+            fields, self.node, self.ctx, self.path,
+        ))
+    }
+}
+
+/// Placeholder of a missing field, which deserializes into a value like `1`
+/// or `""` of whatever type requested, so that deserializing can go on to
+/// find other missing fields. Integers are `1` to pass `NonZero` types.
+///
+/// Structs look for their own missing fields, while nothing else like
+/// variants of an enum is looked into.
+struct Missing<'a> {
+    ctx: Context<'a>,
+    /// Full key of the field, `None` for placeholders inside another one.
+    path: Option<String>,
+}
+
+impl<'a> Missing<'a> {
+    fn new(ctx: Context<'a>, path: String) -> Self {
+        Self {
+            ctx,
+            path: Some(path),
+        }
+    }
+
+    fn silent(ctx: Context<'a>) -> Self {
+        Self { ctx, path: None }
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for Missing<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Missing<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_unit()
+    }
+
+    fn deserialize_bool<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_bool(false)
+    }
+
+    fn deserialize_i64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_i64(1)
+    }
+
+    fn deserialize_u64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_u64(1)
+    }
+
+    fn deserialize_f64<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_char('\0')
+    }
+
+    fn deserialize_str<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_str("")
+    }
+
+    fn deserialize_bytes<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_bytes(&[])
+    }
+
+    fn deserialize_option<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(0, vis)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let ctx = self.ctx;
+        vis.visit_seq(SeqDeserializer::new(
+            (0..len).map(move |_| Missing::silent(ctx)),
+        ))
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, vis)
+    }
+
+    fn deserialize_map<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        vis.visit_map(MapDeserializer::new(std::iter::empty::<(String, Missing)>()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.path {
+            // Same as an empty struct, to report its required fields.
+            Some(path) => Deserializer::with_path(Cow::Owned(Node::default()), self.ctx, path)
+                .deserialize_struct(name, fields, vis),
+            None => {
+                let ctx = self.ctx;
+                vis.visit_map(MapDeserializer::new(
+                    fields
+                        .iter()
+                        .map(move |&field| (field, Missing::silent(ctx))),
+                ))
+            }
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // Any variant works since the value is dropped, nothing under it is
+        // recorded.
+        vis.visit_enum(MissingVariant {
+            ctx: self.ctx,
+            variant: variants.first().copied().unwrap_or_default(),
+        })
+    }
+
+    forward_to_deserialize_any! {
+        unit unit_struct identifier ignored_any
+    }
+
+    fn deserialize_i8<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(vis)
+    }
+
+    fn deserialize_i16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(vis)
+    }
+
+    fn deserialize_i32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_i64(vis)
+    }
+
+    fn deserialize_u8<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_u64(vis)
+    }
+
+    fn deserialize_u16<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_u64(vis)
+    }
+
+    fn deserialize_u32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_u64(vis)
+    }
+
+    fn deserialize_f32<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_f64(vis)
+    }
+
+    fn deserialize_string<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(vis)
+    }
+
+    fn deserialize_byte_buf<V>(self, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(vis)
+    }
+}
+
+/// Placeholder variant of a missing enum.
+struct MissingVariant<'a> {
+    ctx: Context<'a>,
+    variant: &'static str,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for MissingVariant<'a> {
+    type Error = Error;
+    type Variant = Missing<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let v = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((v, Missing::silent(self.ctx)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Missing<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, vis: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple(self, len, vis)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        vis: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_struct(self, "", fields, vis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::ffi::OsString;
+
+    use serde::Deserialize;
 
     use super::*;
+    use crate::builder::Options;
+
+    #[derive(Deserialize, Default, PartialEq, Debug)]
+    #[serde(default)]
+    struct TestStruct {
+        a: i64,
+        b: bool,
+        c: String,
+        d: EmbedStruct,
+    }
+
+    #[derive(Deserialize, Default, PartialEq, Debug)]
+    #[serde(default)]
+    struct EmbedStruct {
+        aa: f32,
+        bb: String,
+    }
+
+    #[test]
+    fn test_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("A", Some("123")),
+                ("B", Some("true")),
+                ("C", Some("Hello, test")),
+                ("D_AA", Some("1.2")),
+                ("D_BB", Some("Hello, embed")),
+            ],
+            || {
+                let t: TestStruct = from_env().expect("must success");
+                assert_eq!(
+                    t,
+                    TestStruct {
+                        a: 123,
+                        b: true,
+                        c: "Hello, test".to_string(),
+                        d: EmbedStruct {
+                            aa: 1.2,
+                            bb: "Hello, embed".to_string()
+                        }
+                    }
+                )
+            },
+        )
+    }
+
+    /// This test is ported from [softprops/envy](https://github.com/softprops/envy/blob/801d81e7c3e443470e110bf4e34460acba113476/src/lib.rs#L410)
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    struct Foo {
+        bar: String,
+        baz: bool,
+        zoom: Option<u16>,
+        doom: Vec<u64>,
+        boom: Vec<String>,
+        #[serde(default = "default_kaboom")]
+        kaboom: u16,
+        #[serde(default)]
+        debug_mode: bool,
+        provided: Option<String>,
+        newtype: CustomNewType,
+        boom_zoom: bool,
+        #[serde(default = "default_bool")]
+        mode_xx: bool,
+    }
+
+    fn default_bool() -> bool {
+        true
+    }
+
+    fn default_kaboom() -> u16 {
+        8080
+    }
+
+    #[derive(Deserialize, Debug, PartialEq, Eq, Default)]
+    struct CustomNewType(u32);
+
+    #[test]
+    fn test_ported_from_envy() {
+        temp_env::with_vars(
+            vec![
+                ("BAR", Some("test")),
+                ("BAZ", Some("true")),
+                ("DOOM", Some("1, 2, 3 ")),
+                // Empty string should result in empty vector.
+                ("BOOM", Some("")),
+                ("SIZE", Some("small")),
+                ("PROVIDED", Some("test")),
+                ("NEWTYPE", Some("42")),
+                ("boom_zoom", Some("true")),
+                ("mode_xx", Some("false")),
+            ],
+            || {
+                let actual: Foo = from_env().expect("must success");
+                assert_eq!(
+                    actual,
+                    Foo {
+                        bar: String::from("test"),
+                        baz: true,
+                        zoom: None,
+                        doom: vec![1, 2, 3],
+                        boom: vec![],
+                        kaboom: 8080,
+                        debug_mode: false,
+                        provided: Some(String::from("test")),
+                        newtype: CustomNewType(42),
+                        boom_zoom: true,
+                        mode_xx: false
+                    }
+                )
+            },
+        )
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct TestStructAlias {
+        #[serde(alias = "meta_log_level")]
+        log_level: String,
+    }
+
+    // We are not support alias now.
+    #[test]
+    #[ignore]
+    fn test_from_env_alias() {
+        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
+            let t: TestStructAlias = from_env().expect("must success");
+            assert_eq!(
+                t,
+                TestStructAlias {
+                    log_level: "DEBUG".to_string()
+                }
+            )
+        })
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct TestStructFlat {
+        meta_log_level: String,
+    }
+
+    #[test]
+    fn test_from_env_flat() {
+        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
+            let t: TestStructFlat = from_env().expect("must success");
+            assert_eq!(
+                t,
+                TestStructFlat {
+                    meta_log_level: "DEBUG".to_string()
+                }
+            )
+        })
+    }
+
+    #[test]
+    fn test_from_env_flat_upper() {
+        temp_env::with_vars(vec![("META_LOG_LEVEL", Some("DEBUG"))], || {
+            let t: TestStructFlat = from_env().expect("must success");
+            assert_eq!(
+                t,
+                TestStructFlat {
+                    meta_log_level: "DEBUG".to_string()
+                }
+            )
+        })
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct TestStructFlatWithDefault {
+        meta_log_level: String,
+    }
+
+    impl Default for TestStructFlatWithDefault {
+        fn default() -> Self {
+            Self {
+                meta_log_level: "INFO".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_env_flat_with_default() {
+        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
+            let t: TestStructFlatWithDefault = from_env().expect("must success");
+            assert_eq!(
+                t,
+                TestStructFlatWithDefault {
+                    meta_log_level: "DEBUG".to_string()
+                }
+            )
+        })
+    }
+
+    #[test]
+    fn test_from_env_flat_upper_with_default() {
+        temp_env::with_vars(vec![("META_LOG_LEVEL", Some("DEBUG"))], || {
+            let t: TestStructFlatWithDefault = from_env().expect("must success");
+            assert_eq!(
+                t,
+                TestStructFlatWithDefault {
+                    meta_log_level: "DEBUG".to_string()
+                }
+            )
+        })
+    }
+
+    #[test]
+    fn test_from_env_as_map() {
+        temp_env::with_vars(vec![("METASRV_LOG_LEVEL", Some("DEBUG"))], || {
+            let t: HashMap<String, String> = from_env().expect("must success");
+            assert_eq!(t["metasrv_log_level"], "DEBUG".to_string())
+        })
+    }
+
+    #[test]
+    fn test_from_iter_map_nested_keys() {
+        let expected = |v: &[(&str, &str)]| -> HashMap<String, String> {
+            v.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        // Nested keys without value never become entries, whatever other
+        // keys are supplied.
+        let t: HashMap<String, String> = from_iter([("APP_NAME", "x")]).expect("must success");
+        assert_eq!(t, expected(&[("app_name", "x")]));
+
+        let t: HashMap<String, String> =
+            from_iter([("APP_NAME", "x"), ("A", "1")]).expect("must success");
+        assert_eq!(t, expected(&[("a", "1"), ("app_name", "x")]));
+
+        let t: HashMap<String, String> =
+            from_iter([("APP_NAME_LEN", "3"), ("APP_DB_HOST", "h")]).expect("must success");
+        assert_eq!(t, expected(&[("app_db_host", "h"), ("app_name_len", "3")]));
+
+        let t: HashMap<String, u32> = from_iter([("APP_NAME_LEN", "3")]).expect("must success");
+        assert_eq!(t, HashMap::from([("app_name_len".to_string(), 3)]));
+
+        let err = from_iter::<_, _, HashMap<String, u32>>([("APP_NAME", "x")])
+            .expect_err("name is invalid");
+        assert!(err.to_string().contains("`app_name`"), "{err}");
+    }
+
+    #[test]
+    fn test_from_iter_as_btree_map() {
+        let t: BTreeMap<String, String> = from_iter([
+            ("METASRV_LOG_LEVEL", "DEBUG"),
+            ("METASRV_LOG", "stdout"),
+            ("PORT", "80"),
+            ("APP_NAME", "app"),
+            ("APP_1", "a"),
+        ])
+        .expect("must success");
+        assert_eq!(
+            t.into_iter().collect::<Vec<_>>(),
+            [
+                ("app_1", "a"),
+                ("app_name", "app"),
+                ("metasrv_log", "stdout"),
+                ("metasrv_log_level", "DEBUG"),
+                ("port", "80"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+
+        // Nested keys without value like `app` are not entries.
+        let t: BTreeMap<String, u32> =
+            from_iter([("PORT", "80"), ("APP_1", "1"), ("APP_NAME_LEN", "3")])
+                .expect("must success");
+        assert_eq!(
+            t.keys().collect::<Vec<_>>(),
+            ["app_1", "app_name_len", "port"]
+        );
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct EnumNewtype {
+        bar: String,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct ExternallyEnumStruct {
+        foo: ExternallyEnum,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum ExternallyEnum {
+        X,
+        Y(EnumNewtype),
+        Z { a: i32 },
+        W { inner: EmbedStruct, b: bool },
+        Pair(u32, u32),
+    }
+
+    #[test]
+    fn test_from_env_externally_enum() {
+        temp_env::with_vars(vec![("FOO", Some("X"))], || {
+            let t: ExternallyEnumStruct = from_env().expect("must success");
+            assert_eq!(t.foo, ExternallyEnum::X)
+        });
+
+        temp_env::with_vars(vec![("FOO", Some("Y")), ("FOO_BAR", Some("xxx"))], || {
+            let t: ExternallyEnumStruct = from_env().expect("must success");
+            assert_eq!(
+                t.foo,
+                ExternallyEnum::Y(EnumNewtype {
+                    bar: "xxx".to_string()
+                })
+            )
+        });
+
+        temp_env::with_vars(vec![("FOO", Some("Z")), ("FOO_A", Some("1"))], || {
+            let t: ExternallyEnumStruct = from_env().expect("must success");
+            assert_eq!(t.foo, ExternallyEnum::Z { a: 1 })
+        });
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct InternallyEnumStruct {
+        foo: InternallyEnum,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(tag = "type")]
+    enum InternallyEnum {
+        X,
+        Y(EnumNewtype),
+        Z { a: i32 },
+    }
+
+    // Internally tagged enums are buffered via `deserialize_any`, which works
+    // since nested keys are visited as map. Values are still untyped, see
+    // https://github.com/serde-rs/serde/issues/2187
+    #[test]
+    fn test_from_env_internally_enum() {
+        temp_env::with_vars(vec![("FOO_TYPE", Some("X"))], || {
+            let t: InternallyEnumStruct = from_env().expect("must success");
+            assert_eq!(t.foo, InternallyEnum::X)
+        });
+
+        temp_env::with_vars(
+            vec![("FOO_TYPE", Some("Y")), ("FOO_BAR", Some("xxx"))],
+            || {
+                let t: InternallyEnumStruct = from_env().expect("must success");
+                assert_eq!(
+                    t.foo,
+                    InternallyEnum::Y(EnumNewtype {
+                        bar: "xxx".to_string()
+                    })
+                )
+            },
+        );
+
+        temp_env::with_vars(vec![("FOO_TYPE", Some("Z")), ("FOO_A", Some("1"))], || {
+            let t: InternallyEnumStruct = from_env().expect("must success");
+            assert_eq!(t.foo, InternallyEnum::Z { a: 1 })
+        });
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Eq)]
+    struct DoubleOptionOuter {
+        inner: Option<DoubleOptionInner>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Eq)]
+    struct DoubleOptionInner {
+        val: Option<u8>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Eq)]
+    struct OptionStructOuter {
+        inner: Option<OptionStructInner>,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug, Eq)]
+    struct OptionStructInner {
+        a: Option<u8>,
+        b: Option<u8>,
+    }
+
+    #[test]
+    fn test_option_struct_absent() {
+        let t: OptionStructOuter = from_iter([("OTHER", "1")]).expect("must success");
+        assert_eq!(t.inner, None);
+
+        let t: OptionStructOuter =
+            from_iter([("INNER_A", ""), ("INNER_B", "")]).expect("must success");
+        assert_eq!(t.inner, None);
+
+        let t: OptionStructOuter = from_iter([("INNER_A", "1")]).expect("must success");
+        assert_eq!(
+            t.inner,
+            Some(OptionStructInner {
+                a: Some(1),
+                b: None
+            })
+        );
+
+        let t: OptionStructOuter =
+            from_iter([("INNER_A", "1"), ("INNER_B", "2")]).expect("must success");
+        assert_eq!(
+            t.inner,
+            Some(OptionStructInner {
+                a: Some(1),
+                b: Some(2)
+            })
+        );
+    }
+
+    #[test]
+    fn double_option() {
+        temp_env::with_var("INNER_VAL", Some("2"), || {
+            let t: DoubleOptionOuter = from_env().expect("must success");
+            assert_eq!(
+                t,
+                DoubleOptionOuter {
+                    inner: Some(DoubleOptionInner { val: Some(2) })
+                }
+            )
+        })
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFlatten {
+        silent: Option<String>,
+        pub_hosted_url: Option<String>,
+        #[serde(rename = "pub")]
+        pub_: TestPub,
+        #[serde(flatten)]
+        inner: TestFlattenInner,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestPub {
+        hosted: TestPubInner,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestPubInner {
+        url: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFlattenInner {
+        port: u32,
+        enable: bool,
+        t: Option<u64>,
+        t2: String,
+        foo: Vec<u32>,
+        foo2: Vec<u32>,
+        foo3: Option<Vec<u32>>,
+    }
+
+    #[test]
+    fn test_from_env_flatten() {
+        temp_env::with_vars(
+            vec![
+                ("port", Some("123")),
+                ("enable", Some("True")),
+                ("enable", Some("False")),
+                ("silent", Some("")),
+                ("t", Some("18446744073709551615")),
+                ("t2", Some("18446744073709551616")),
+                ("PUB_HOSTED_URL", Some("https://pub.dev")),
+                ("foo", Some("1,2,3")),
+                ("foo2", Some("1,2,")),
+                ("foo3", Some("1,")),
+                ("e", Some("X")),
+                ("e", Some("Z")),
+                ("e_a", Some("1")),
+            ],
+            || {
+                let n = Node::from_env();
+
+                let t: TestFlatten = TestFlatten::deserialize(Deserializer::new(
+                    n,
+                    Context::new(&Options::default()),
+                ))
+                .expect("must success");
+                dbg!(&t);
+                assert_eq!(t.inner.port, 123);
+                assert!(!t.inner.enable);
+                assert_eq!(t.pub_.hosted.url, "https://pub.dev");
+                // Already read by field `pub`.
+                assert_eq!(t.pub_hosted_url, None);
+                assert_eq!(t.inner.foo, vec![1, 2, 3]);
+            },
+        )
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestOs {
+        name: String,
+        data: serde_bytes::ByteBuf,
+    }
+
+    #[test]
+    fn test_from_iter_os() {
+        let t: TestOs = from_iter_os([
+            (OsString::from("NAME"), OsString::from("Hello")),
+            (OsString::from("DATA"), OsString::from("World")),
+        ])
+        .expect("must success");
+        assert_eq!(
+            t,
+            TestOs {
+                name: "Hello".to_string(),
+                data: serde_bytes::ByteBuf::from("World")
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_from_iter_os_invalid_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = vec![0x66, 0x6f, 0x80];
+        let t: TestOs = from_iter_os([
+            (OsString::from("NAME"), OsString::from_vec(invalid.clone())),
+            (OsString::from("DATA"), OsString::from_vec(invalid.clone())),
+        ])
+        .expect("must success");
+        assert_eq!(
+            t,
+            TestOs {
+                name: "fo\u{FFFD}".to_string(),
+                data: serde_bytes::ByteBuf::from(invalid)
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Matrix([[f64; 2]; 2]);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestMatrix {
+        m: Matrix,
+    }
+
+    #[test]
+    fn test_from_iter_nested_array() {
+        let t: TestMatrix = from_iter([
+            ("M_0_0", "1.0"),
+            ("M_0_1", "2.0"),
+            ("M_1_0", "3.0"),
+            ("M_1_1", "4.0"),
+        ])
+        .expect("must success");
+        assert_eq!(t.m, Matrix([[1.0, 2.0], [3.0, 4.0]]));
+
+        let err =
+            from_iter::<_, _, TestMatrix>([("M_0_0", "1.0"), ("M_0_1", "2.0"), ("M_1_0", "3.0")])
+                .expect_err("must fail");
+        assert!(err.to_string().contains("invalid length 1"), "{err}");
+
+        let err = from_iter::<_, _, TestMatrix>([
+            ("M_0_0", "1.0"),
+            ("M_0_1", "2.0"),
+            ("M_2_0", "3.0"),
+            ("M_2_1", "4.0"),
+        ])
+        .expect_err("must fail");
+        assert!(
+            err.to_string().contains("missing indexed element 1"),
+            "{err}"
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestTrim {
+        tags: Vec<String>,
+        pair: (String, String),
+    }
+
+    #[test]
+    fn test_trim_seq_elements() {
+        let vars = [("TAGS", " a , b "), ("PAIR", " c , d ")];
+
+        let t: TestTrim = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestTrim {
+                tags: vec!["a".to_string(), "b".to_string()],
+                pair: ("c".to_string(), "d".to_string()),
+            }
+        );
+
+        let t: TestTrim = EnvDeserializer::new()
+            .trim_seq_elements(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            TestTrim {
+                tags: vec![" a ".to_string(), " b ".to_string()],
+                pair: (" c ".to_string(), " d ".to_string()),
+            }
+        );
+
+        // Padded numbers are trimmed as scalars anyway.
+        let vars = [("DOOM", "1, 2, 3 ")];
+        let t: HashMap<String, Vec<u64>> = from_iter(vars).expect("must success");
+        assert_eq!(t["doom"], vec![1, 2, 3]);
+        let t: HashMap<String, Vec<u64>> = EnvDeserializer::new()
+            .trim_seq_elements(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t["doom"], vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "secrecy")]
+    #[derive(Deserialize)]
+    struct TestSecret {
+        user: String,
+        password: secrecy::SecretString,
+    }
+
+    #[test]
+    #[cfg(feature = "secrecy")]
+    fn test_from_iter_secret() {
+        use secrecy::ExposeSecret;
+
+        let t: TestSecret =
+            from_iter([("USER", "admin"), ("PASSWORD", "hunter2")]).expect("must success");
+        assert_eq!(t.user, "admin");
+        assert_eq!(t.password.expose_secret(), "hunter2");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestEmpty {
+        empty: Option<String>,
+        absent: Option<String>,
+        nonempty: Option<String>,
+    }
+
+    #[test]
+    fn test_empty_string_is_none() {
+        let vars = [("EMPTY", ""), ("NONEMPTY", "Hello")];
+
+        let t: TestEmpty = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestEmpty {
+                empty: None,
+                absent: None,
+                nonempty: Some("Hello".to_string()),
+            }
+        );
+
+        let t: TestEmpty = EnvDeserializer::new()
+            .empty_string_is_none(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            TestEmpty {
+                empty: Some(String::new()),
+                absent: None,
+                nonempty: Some("Hello".to_string()),
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestReport {
+        a: i64,
+        #[serde(default)]
+        b: bool,
+        password: String,
+        d: EmbedStruct,
+    }
+
+    #[test]
+    fn test_from_env_with_report() {
+        use crate::report::Source;
+
+        temp_env::with_vars(
+            vec![
+                ("A", Some("123")),
+                ("PASSWORD", Some("hunter2")),
+                ("D_AA", Some("1.2")),
+            ],
+            || {
+                let (t, report): (TestReport, _) = EnvDeserializer::new()
+                    .redact(|key| key == "PASSWORD")
+                    .from_env_with_report()
+                    .expect("must success");
+                assert_eq!(t.a, 123);
+
+                let a = report.get("A").expect("must exist");
+                assert_eq!(a.source, Source::Env);
+                assert_eq!(a.value.as_deref(), Some("123"));
+
+                let b = report.get("B").expect("must exist");
+                assert_eq!(b.source, Source::Default);
+                assert_eq!(b.value, None);
+
+                let password = report.get("PASSWORD").expect("must exist");
+                assert_eq!(password.source, Source::Env);
+                assert!(password.redacted);
+                assert_eq!(password.value, None);
+
+                assert_eq!(report.get("D_AA").unwrap().source, Source::Env);
+                assert_eq!(report.get("D_BB").unwrap().source, Source::Default);
+                assert!(report.get("D").is_none());
+                assert!(!report.to_string().contains("hunter2"));
+            },
+        )
+    }
+
+    #[test]
+    fn test_prefix_separator_empty() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            home: String,
+            path: String,
+        }
+
+        let vars = [
+            ("APPHOME", "/home"),
+            ("APPPATH", "/bin"),
+            ("HOME", "/other"),
+        ];
+        let t: Test = EnvDeserializer::new()
+            .prefix_separator("")
+            .from_iter_with_prefix(vars, "APP")
+            .expect("must success");
+        assert_eq!(
+            t,
+            Test {
+                home: "/home".to_string(),
+                path: "/bin".to_string(),
+            }
+        );
+
+        temp_env::with_vars(
+            [
+                ("SERDEENVHOME", Some("/home")),
+                ("SERDEENVPATH", Some("/bin")),
+            ],
+            || {
+                let t: Test = EnvDeserializer::new()
+                    .prefix_separator("")
+                    .from_prefix("SERDEENV")
+                    .expect("must success");
+                assert_eq!(t.home, "/home");
+                assert_eq!(t.path, "/bin");
+            },
+        );
+    }
+
+    #[test]
+    fn test_prefix_separator() {
+        let vars = [
+            ("APP__A", "123"),
+            ("APP__B", "true"),
+            ("APP__C", "Hello, test"),
+            ("APP__D_AA", "1.2"),
+            ("APP__D_BB", "Hello, nested"),
+            ("APP_A", "456"),
+        ];
+
+        let t: TestStruct = EnvDeserializer::new()
+            .prefix_separator("__")
+            .from_iter_with_prefix(vars, "APP")
+            .expect("must success");
+        assert_eq!(
+            t,
+            TestStruct {
+                a: 123,
+                b: true,
+                c: "Hello, test".to_string(),
+                d: EmbedStruct {
+                    aa: 1.2,
+                    bb: "Hello, nested".to_string(),
+                },
+            }
+        )
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestIndexedSeq {
+        entries: Vec<HashMap<String, String>>,
+    }
+
+    #[test]
+    fn test_from_iter_indexed_seq_of_maps() {
+        let vars = [
+            ("ENTRIES_0_A", "1"),
+            ("ENTRIES_0_B", "2"),
+            ("ENTRIES_1_A", "3"),
+        ];
+
+        let t: TestIndexedSeq = from_iter(vars).expect("must success");
+        assert_eq!(
+            t.entries,
+            vec![
+                HashMap::from([
+                    ("a".to_string(), "1".to_string()),
+                    ("b".to_string(), "2".to_string()),
+                ]),
+                HashMap::from([("a".to_string(), "3".to_string())]),
+            ]
+        )
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestCollision {
+        a: String,
+        d: EmbedStruct,
+    }
+
+    #[test]
+    fn test_detect_collisions() {
+        let vars = [
+            ("A", "scalar"),
+            ("A_B", "nested"),
+            ("D_AA", "1.2"),
+            ("D_BB", "Hello"),
+        ];
+
+        let t: TestCollision = from_iter(vars).expect("must success");
+        assert_eq!(t.a, "scalar");
+
+        let err = EnvDeserializer::new()
+            .detect_collisions(true)
+            .from_iter::<_, _, TestCollision>(vars)
+            .expect_err("must fail");
+        assert!(err.to_string().contains("expected a scalar"), "{err}");
+
+        let vars = [
+            ("A", "scalar"),
+            ("D", "scalar"),
+            ("D_AA", "1.2"),
+            ("D_BB", "Hello"),
+        ];
+        let err = EnvDeserializer::new()
+            .detect_collisions(true)
+            .from_iter::<_, _, TestCollision>(vars)
+            .expect_err("must fail");
+        assert!(err.to_string().contains("expected a struct"), "{err}");
+    }
+
+    #[test]
+    fn test_from_iter_json_value() {
+        let vars = [
+            ("A", "123"),
+            ("B", "true"),
+            ("C", "Hello, test"),
+            ("D_AA", "1.2"),
+            ("D_BB", "Hello"),
+        ];
+
+        let t: serde_json::Value = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            serde_json::json!({
+                "a": 123,
+                "b": true,
+                "c": ["Hello", "test"],
+                "d": {
+                    "aa": "1.2",
+                    "bb": "Hello",
+                },
+            })
+        )
+    }
+
+    #[test]
+    fn test_from_iter_map_of_json_value() {
+        let vars = [
+            ("NAME", "test"),
+            ("PORT", "8080"),
+            ("DB_HOST", "localhost"),
+            ("DB_POOL_SIZE", "4"),
+        ];
+
+        let t: HashMap<String, serde_json::Value> = from_iter(vars).expect("must success");
+        assert_eq!(t["name"], serde_json::json!("test"));
+        assert_eq!(t["port"], serde_json::json!(8080));
+        assert_eq!(
+            t["db"],
+            serde_json::json!({ "host": "localhost", "pool": { "size": 4 } })
+        );
+        // Flat keys are kept too, which `#[serde(flatten)]` relies on.
+        assert_eq!(t["db_host"], serde_json::json!("localhost"));
+        assert_eq!(t["db_pool"], serde_json::json!({ "size": 4 }));
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestFloat {
+        limit: f64,
+        lower: f64,
+        ratio: f64,
+    }
+
+    #[test]
+    fn test_from_iter_float_special() {
+        let vars = [("LIMIT", "inf"), ("LOWER", "-Infinity"), ("RATIO", "NaN")];
+
+        let t: TestFloat = from_iter(vars).expect("must success");
+        assert_eq!(t.limit, f64::INFINITY);
+        assert_eq!(t.lower, f64::NEG_INFINITY);
+        assert!(t.ratio.is_nan());
+
+        #[derive(Deserialize, Debug)]
+        struct TestFloat32 {
+            limit: f32,
+            lower: f32,
+            ratio: f32,
+        }
+
+        let vars = [("LIMIT", "INF"), ("LOWER", "-inf"), ("RATIO", "nan")];
+        let t: TestFloat32 = from_iter(vars).expect("must success");
+        assert_eq!(t.limit, f32::INFINITY);
+        assert_eq!(t.lower, f32::NEG_INFINITY);
+        assert!(t.ratio.is_nan());
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum TestUntypedValue {
+        Float(f64),
+        Str(String),
+    }
+
+    #[test]
+    fn test_infer_float() {
+        let vars = [("A", "1.5"), ("B", "inf"), ("C", "-inf"), ("D", "infra")];
+
+        let t: HashMap<String, TestUntypedValue> = from_iter(vars).expect("must success");
+        assert_eq!(t["a"], TestUntypedValue::Str("1.5".to_string()));
+        assert_eq!(t["b"], TestUntypedValue::Str("inf".to_string()));
+
+        let t: HashMap<String, TestUntypedValue> = EnvDeserializer::new()
+            .infer_float(true)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t["a"], TestUntypedValue::Float(1.5));
+        assert_eq!(t["b"], TestUntypedValue::Float(f64::INFINITY));
+        assert_eq!(t["c"], TestUntypedValue::Float(f64::NEG_INFINITY));
+        assert_eq!(t["d"], TestUntypedValue::Str("infra".to_string()));
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum TestUntagged {
+        Int(u64),
+        Str(String),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum TestUntaggedStrict {
+        Int(u64),
+        Bool(bool),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestUntaggedField {
+        a: TestUntagged,
+        b: TestUntagged,
+    }
+
+    #[test]
+    fn test_from_iter_untagged() {
+        let vars = [("A", "42"), ("B", "hello")];
+
+        let t: TestUntaggedField = from_iter(vars).expect("must success");
+        assert_eq!(t.a, TestUntagged::Int(42));
+        assert_eq!(t.b, TestUntagged::Str("hello".to_string()));
+
+        // Matches neither variant.
+        let t: Result<HashMap<String, TestUntaggedStrict>, _> = from_iter([("A", "hello")]);
+        assert!(t.is_err());
+    }
+
+    #[test]
+    fn test_from_iter_empty_untyped() {
+        let t: serde_json::Value = from_iter::<_, &str, _>([]).expect("must success");
+        assert_eq!(t, serde_json::Value::Null);
+
+        let vars = [("A", ""), ("B", "x")];
+
+        let t: serde_json::Value = from_iter(vars).expect("must success");
+        assert_eq!(t, serde_json::json!({ "b": "x" }));
+
+        let t: serde_json::Value = EnvDeserializer::new()
+            .empty_string_is_none(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t, serde_json::json!({ "a": "", "b": "x" }));
+
+        let t: HashMap<String, String> = EnvDeserializer::new()
+            .empty_string_is_none(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t["a"], "");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Pair(u32, u32);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestTupleStruct {
+        pair: Pair,
+        other: Pair,
+    }
+
+    #[test]
+    fn test_from_iter_tuple_struct() {
+        let vars = [("PAIR", "1,2"), ("OTHER_0", "3"), ("OTHER_1", "4")];
+
+        let t: TestTupleStruct = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestTupleStruct {
+                pair: Pair(1, 2),
+                other: Pair(3, 4),
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestNestedSeq {
+        matrix: Vec<Vec<u32>>,
+        flat: Vec<u32>,
+    }
+
+    #[test]
+    fn test_from_iter_nested_seq() {
+        let de = EnvDeserializer::new().group_delimiter(';');
+
+        let vars = [("MATRIX", "1,2;3,4"), ("FLAT", "1,2")];
+        let t: TestNestedSeq = de.from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestNestedSeq {
+                matrix: vec![vec![1, 2], vec![3, 4]],
+                flat: vec![1, 2],
+            }
+        );
+
+        let vars = [("MATRIX", "1; 2,3,4;;5;"), ("FLAT", "1")];
+        let t: TestNestedSeq = de.from_iter(vars).expect("must success");
+        assert_eq!(t.matrix, vec![vec![1], vec![2, 3, 4], vec![], vec![5]],);
+
+        let vars = [("MATRIX", "1|2/3|4"), ("FLAT", "1|2")];
+        let t: TestNestedSeq = EnvDeserializer::new()
+            .list_delimiter('|')
+            .group_delimiter('/')
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            TestNestedSeq {
+                matrix: vec![vec![1, 2], vec![3, 4]],
+                flat: vec![1, 2],
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct TestScreamingSnake {
+        log_level: String,
+        d: TestScreamingSnakeInner,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct TestScreamingSnakeInner {
+        aa: f32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+    struct TestScreamingKebab {
+        home: String,
+        path: String,
+    }
+
+    #[test]
+    fn test_from_iter_rename_all() {
+        let vars = [("LOG_LEVEL", "debug"), ("D_AA", "1.5")];
+        let t: TestScreamingSnake = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestScreamingSnake {
+                log_level: "debug".to_string(),
+                d: TestScreamingSnakeInner { aa: 1.5 },
+            }
+        );
+
+        let vars = [("HOME", "/test"), ("PATH", "foo:bar")];
+        let t: TestScreamingKebab = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestScreamingKebab {
+                home: "/test".to_string(),
+                path: "foo:bar".to_string(),
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestOptionSeqOption {
+        vals: Option<Vec<Option<u8>>>,
+    }
+
+    #[test]
+    fn test_from_iter_option_seq_option() {
+        let t: TestOptionSeqOption = from_iter::<_, &str, _>([]).expect("must success");
+        assert_eq!(t.vals, None);
+
+        let t: TestOptionSeqOption = from_iter([("VALS", "")]).expect("must success");
+        assert_eq!(t.vals, None);
+
+        let t: TestOptionSeqOption = from_iter([("VALS", "1,,3")]).expect("must success");
+        assert_eq!(t.vals, Some(vec![Some(1), None, Some(3)]));
+
+        let t: TestOptionSeqOption = from_iter([("VALS", ",2,")]).expect("must success");
+        assert_eq!(t.vals, Some(vec![None, Some(2)]));
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    struct TestKebab {
+        log_level: String,
+        max_conn_count: u32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestKebabCollision {
+        log_level: String,
+        #[serde(rename = "log-level")]
+        log_level_kebab: String,
+    }
+
+    #[test]
+    fn test_from_iter_kebab_case() {
+        let vars = [("LOG_LEVEL", "debug"), ("MAX_CONN_COUNT", "8")];
+        let t: TestKebab = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestKebab {
+                log_level: "debug".to_string(),
+                max_conn_count: 8,
+            }
+        );
+
+        let t: TestKebabCollision = from_iter(vars).expect("must success");
+        assert_eq!(t.log_level, "debug");
+        assert_eq!(t.log_level_kebab, "debug");
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestMissing {
+        a: u32,
+        b: Option<String>,
+        c: (u32, String),
+        d: TestMissingInner,
+        #[serde(default)]
+        e: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestMissingInner {
+        aa: f32,
+        bb: String,
+    }
+
+    #[test]
+    fn test_try_from_env() {
+        temp_env::with_vars(vec![("C", Some("1,x")), ("D_BB", Some("bb"))], || {
+            let err = try_from_env::<TestMissing>().expect_err("must fail");
+            let missing: Vec<_> = err
+                .missing()
+                .iter()
+                .map(|v| (v.field().to_string(), v.key()))
+                .collect();
+            assert_eq!(
+                missing,
+                [
+                    ("a".to_string(), "A".to_string()),
+                    ("aa".to_string(), "D_AA".to_string()),
+                ]
+            );
+            assert!(err.to_string().contains("`a` (env `A`)"), "{err}");
+            assert!(err.to_string().contains("`aa` (env `D_AA`)"), "{err}");
+        });
+
+        temp_env::with_vars(vec![("C", Some("1,x"))], || {
+            let err = try_from_env::<TestMissing>().expect_err("must fail");
+            let keys: Vec<_> = err.missing().iter().map(|v| v.key()).collect();
+            assert_eq!(keys, ["A", "D_AA", "D_BB"]);
+        });
+
+        temp_env::with_vars(
+            vec![
+                ("A", Some("1")),
+                ("C", Some("1,x")),
+                ("D_AA", Some("1.5")),
+                ("D_BB", Some("bb")),
+            ],
+            || {
+                let t = try_from_env::<TestMissing>().expect("must success");
+                assert_eq!(t.d.aa, 1.5);
+            },
+        );
+
+        #[derive(Deserialize, Debug)]
+        struct TestMissingDefault {
+            probe_a: String,
+            #[serde(default)]
+            probe_b: u16,
+        }
+
+        temp_env::with_vars(vec![("PROBE_A", None::<&str>), ("PROBE_B", None)], || {
+            let err = try_from_env::<TestMissingDefault>().expect_err("must fail");
+            let keys: Vec<_> = err.missing().iter().map(|v| v.key()).collect();
+            assert_eq!(keys, ["PROBE_A"]);
+        });
+
+        temp_env::with_vars(vec![("PROBE_A", Some("a")), ("PROBE_B", None)], || {
+            let t = try_from_env::<TestMissingDefault>().expect("must success");
+            assert_eq!(t.probe_a, "a");
+            assert_eq!(t.probe_b, 0);
+        });
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct TestMissingTyped {
+            port: std::num::NonZeroU16,
+            mode: ExternallyEnum,
+            name: String,
+        }
+
+        // Placeholders are neither validated nor looked into.
+        temp_env::with_vars(vec![("PORT", None::<&str>)], || {
+            let err = try_from_env::<TestMissingTyped>().expect_err("must fail");
+            let keys: Vec<_> = err.missing().iter().map(|v| v.key()).collect();
+            assert_eq!(keys, ["MODE", "NAME", "PORT"]);
+        });
+
+        temp_env::with_vars(vec![("PORT", Some("0")), ("MODE", Some("X"))], || {
+            let err = try_from_env::<TestMissingTyped>().expect_err("must fail");
+            assert!(err.missing().is_empty());
+            assert!(err.to_string().contains("non-zero"), "{err}");
+        });
+    }
+
+    #[test]
+    fn test_from_iter_enum_nested_struct_variant() {
+        let vars = [
+            ("FOO", "W"),
+            ("FOO_INNER_AA", "1.0"),
+            ("FOO_INNER_BB", "Hello"),
+            ("FOO_B", "true"),
+        ];
+
+        let t: ExternallyEnumStruct = from_iter(vars).expect("must success");
+        assert_eq!(
+            t.foo,
+            ExternallyEnum::W {
+                inner: EmbedStruct {
+                    aa: 1.0,
+                    bb: "Hello".to_string()
+                },
+                b: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_infer_bool() {
+        let vars = [("ENABLED", "true"), ("DEBUG", "False")];
+
+        let t: serde_json::Value = from_iter(vars).expect("must success");
+        assert_eq!(t, serde_json::json!({ "enabled": true, "debug": false }));
+
+        let t: serde_json::Value = EnvDeserializer::new()
+            .infer_bool(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            serde_json::json!({ "enabled": "true", "debug": "False" })
+        );
+
+        // Typed fields are not affected.
+        let t: HashMap<String, bool> = EnvDeserializer::new()
+            .infer_bool(false)
+            .from_iter([("ENABLED", "true")])
+            .expect("must success");
+        assert!(t["enabled"]);
+    }
+
+    #[test]
+    fn test_from_iter_enum_tuple_variant() {
+        let vars = [("FOO", "Pair"), ("FOO_0", "1"), ("FOO_1", "2")];
+        let t: ExternallyEnumStruct = from_iter(vars).expect("must success");
+        assert_eq!(t.foo, ExternallyEnum::Pair(1, 2));
+
+        let vars = [("FOO", "Pair"), ("FOO_0", "1"), ("FOO_2", "2")];
+        let err = from_iter::<_, _, ExternallyEnumStruct>(vars).expect_err("must fail");
+        assert!(
+            err.to_string().contains("missing indexed element 1"),
+            "{err}"
+        );
+
+        let vars = [("FOO", "Pair"), ("FOO_0", "1")];
+        let err = from_iter::<_, _, ExternallyEnumStruct>(vars).expect_err("must fail");
+        assert!(err.to_string().contains("invalid length 1"), "{err}");
+
+        let vars = [("FOO", "Pair")];
+        let err = from_iter::<_, _, ExternallyEnumStruct>(vars).expect_err("must fail");
+        assert!(err.to_string().contains("indexed elements"), "{err}");
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct AdjacentlyEnumStruct {
+        foo: AdjacentlyEnum,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(tag = "t", content = "c")]
+    enum AdjacentlyEnum {
+        X,
+        Y(EnumNewtype),
+        Z { a: i32 },
+    }
+
+    #[test]
+    fn test_from_iter_adjacently_enum() {
+        let t: AdjacentlyEnumStruct = from_iter([("FOO_T", "X")]).expect("must success");
+        assert_eq!(t.foo, AdjacentlyEnum::X);
+
+        let vars = [("FOO_T", "Y"), ("FOO_C_BAR", "xxx")];
+        let t: AdjacentlyEnumStruct = from_iter(vars).expect("must success");
+        assert_eq!(
+            t.foo,
+            AdjacentlyEnum::Y(EnumNewtype {
+                bar: "xxx".to_string()
+            })
+        );
+
+        let vars = [("FOO_T", "Z"), ("FOO_C_A", "1")];
+        let t: AdjacentlyEnumStruct = from_iter(vars).expect("must success");
+        assert_eq!(t.foo, AdjacentlyEnum::Z { a: 1 });
+
+        // Flattened, the content `c` comes before the tag `t`, so serde
+        // buffers it via `deserialize_any`.
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Flatten {
+            #[serde(flatten)]
+            foo: AdjacentlyEnum,
+        }
+
+        let t: Flatten = from_iter([("C_A", "1"), ("T", "Z")]).expect("must success");
+        assert_eq!(t.foo, AdjacentlyEnum::Z { a: 1 });
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestBorrowed<'a> {
+        #[serde(borrow)]
+        config_dir: &'a std::path::Path,
+        name: &'a str,
+        tags: Vec<&'a str>,
+        d: TestBorrowedInner<'a>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestBorrowedInner<'a> {
+        bb: &'a str,
+    }
+
+    #[test]
+    fn test_from_node_borrowed() {
+        let node: Node = [
+            ("CONFIG_DIR", "/etc/app"),
+            ("NAME", "app"),
+            ("TAGS_0", "a"),
+            ("TAGS_1", "b"),
+            ("D_BB", "Hello"),
+        ]
+        .into_iter()
+        .collect();
+
+        let t: TestBorrowed = from_node(&node).expect("must success");
+        assert_eq!(t.config_dir, std::path::Path::new("/etc/app"));
+        assert_eq!(t.tags, ["a", "b"]);
+        assert_eq!(t.d.bb, "Hello");
+
+        // Values are borrowed from node without allocation.
+        let config_dir = node.get("config_dir").unwrap().value();
+        assert_eq!(
+            t.config_dir.as_os_str().as_encoded_bytes().as_ptr(),
+            config_dir.as_ptr()
+        );
+        assert_eq!(t.name.as_ptr(), node.get("name").unwrap().value().as_ptr());
+        assert_eq!(t.d.bb.as_ptr(), node.get("d_bb").unwrap().value().as_ptr());
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestWide {
+        f0: u32,
+        f1: u32,
+        f2: u32,
+        f3: u32,
+        f4: u32,
+        f5: u32,
+        f6: u32,
+        f7: u32,
+        f8: u32,
+        f9: u32,
+        f10: u32,
+        f11: u32,
+        f12: u32,
+        f13: u32,
+        f14: u32,
+        f15: u32,
+        f16: u32,
+        f17: u32,
+        f18: u32,
+        f19: u32,
+        f20: u32,
+        f21: u32,
+        f22: u32,
+        f23: u32,
+        f24: u32,
+        f25: u32,
+        f26: u32,
+        f27: u32,
+        f28: u32,
+        f29: u32,
+        f30: u32,
+        f31: u32,
+        f32: u32,
+        f33: u32,
+        f34: u32,
+        f35: u32,
+        f36: u32,
+        f37: u32,
+        f38: u32,
+        f39: u32,
+        f40: u32,
+        f41: u32,
+        f42: u32,
+        f43: u32,
+        f44: u32,
+        f45: u32,
+        f46: u32,
+        f47: u32,
+        f48: u32,
+        f49: u32,
+        nested: TestWideNested,
+        nested_url: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestWideNested {
+        url: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_from_iter_wide() {
+        let mut vars: Vec<_> = (0..50).map(|i| (format!("F{i}"), i.to_string())).collect();
+        vars.push(("NESTED_URL".to_string(), "localhost".to_string()));
+        vars.push(("NESTED_PORT".to_string(), "80".to_string()));
+
+        let t: TestWide = from_iter(vars).expect("must success");
+        assert_eq!(t.f0, 0);
+        assert_eq!(t.f25, 25);
+        assert_eq!(t.f49, 49);
+        // Overlapped keys both see the shared child.
+        assert_eq!(t.nested.url, "localhost");
+        assert_eq!(t.nested.port, 80);
+        assert_eq!(t.nested_url, "localhost");
+    }
+
+    #[test]
+    fn test_from_node_map_twice() {
+        let node: Node = [("A", "1"), ("B_C", "2"), ("B_D_E", "3")]
+            .into_iter()
+            .collect();
+
+        let first: HashMap<String, String> = from_node(&node).expect("must success");
+        let second: HashMap<String, String> = from_node(&node).expect("must success");
+        assert_eq!(first, second);
+        assert_eq!(first["a"], "1");
+        assert_eq!(first["b_c"], "2");
+        assert_eq!(first["b_d_e"], "3");
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestRawNode {
+        name: String,
+        #[serde(flatten)]
+        raw: Node,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestPlugin {
+        url: String,
+        port: u16,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_from_iter_raw_node() {
+        let vars = [
+            ("NAME", "app"),
+            ("PLUGIN_URL", "localhost"),
+            ("PLUGIN_PORT", "80"),
+            ("PLUGIN_TAGS", "a,b"),
+        ];
+
+        let t: TestRawNode = from_iter(vars).expect("must success");
+        assert_eq!(t.name, "app");
+        assert!(t.raw.get("name").is_none());
+
+        let plugin: TestPlugin =
+            from_node(t.raw.get("plugin").expect("plugin must exist")).expect("must success");
+        assert_eq!(
+            plugin,
+            TestPlugin {
+                url: "localhost".to_string(),
+                port: 80,
+                tags: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+
+        // Values are joined by `,` whatever the list delimiter is.
+        let vars = [("NAME", "app"), ("PLUGIN_TAGS", "a;b")];
+        let t: TestRawNode = EnvDeserializer::new()
+            .list_delimiter(';')
+            .from_iter(vars)
+            .expect("must success");
+        let tags = t.raw.get("plugin_tags").expect("tags must exist");
+        assert_eq!(tags.value(), "a,b");
+        let tags: Vec<String> = from_node(tags).expect("must success");
+        assert_eq!(tags, ["a", "b"]);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFlattenDeep {
+        name: String,
+        #[serde(flatten)]
+        inner: TestFlattenDeepInner,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFlattenDeepInner {
+        log_file_path: String,
+        server: TestServer,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestServer {
+        port: u16,
+        tls: TestTls,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestTls {
+        cert: String,
+    }
+
+    #[test]
+    fn test_from_iter_flatten_deep() {
+        let vars = [
+            ("NAME", "app"),
+            ("LOG_FILE_PATH", "/var/log/app"),
+            ("SERVER_PORT", "443"),
+            ("SERVER_TLS_CERT", "/etc/cert.pem"),
+        ];
+
+        let t: TestFlattenDeep = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestFlattenDeep {
+                name: "app".to_string(),
+                inner: TestFlattenDeepInner {
+                    log_file_path: "/var/log/app".to_string(),
+                    server: TestServer {
+                        port: 443,
+                        tls: TestTls {
+                            cert: "/etc/cert.pem".to_string(),
+                        },
+                    },
+                },
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestQuoted {
+        greeting: String,
+        tags: Vec<String>,
+        quoted_tags: Vec<String>,
+        single: String,
+    }
+
+    #[test]
+    fn test_from_iter_quoted() {
+        let vars = [
+            ("GREETING", "\"Hello, world\""),
+            ("TAGS", "a,b"),
+            ("QUOTED_TAGS", "\"a,b\""),
+            ("SINGLE", "'x'"),
+        ];
+
+        let t: TestQuoted = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestQuoted {
+                greeting: "Hello, world".to_string(),
+                tags: vec!["a".to_string(), "b".to_string()],
+                quoted_tags: vec!["a,b".to_string()],
+                single: "x".to_string(),
+            }
+        );
+
+        let t: serde_json::Value = from_iter(vars).expect("must success");
+        assert_eq!(t["greeting"], "Hello, world");
+        assert_eq!(t["tags"], serde_json::json!(["a", "b"]));
+        assert_eq!(t["quoted"]["tags"], "a,b");
+
+        // Unmatched quotes are kept.
+        let t: HashMap<String, String> =
+            from_iter([("A", "\"a'"), ("B", "\"")]).expect("must success");
+        assert_eq!(t["a"], "\"a'");
+        assert_eq!(t["b"], "\"");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestRadix {
+        mask: u8,
+        flags: u32,
+        mode: u16,
+        offset: i32,
+        count: u64,
+    }
+
+    #[test]
+    fn test_from_iter_radix() {
+        let vars = [
+            ("MASK", "0xFF"),
+            ("FLAGS", "0b1010"),
+            ("MODE", "0o755"),
+            ("OFFSET", "-0x10"),
+            ("COUNT", "42"),
+        ];
+
+        let t: TestRadix = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestRadix {
+                mask: 0xFF,
+                flags: 0b1010,
+                mode: 0o755,
+                offset: -0x10,
+                count: 42,
+            }
+        );
+
+        let vars = [
+            ("MASK", "0xZZ"),
+            ("FLAGS", "0"),
+            ("MODE", "0"),
+            ("OFFSET", "0"),
+            ("COUNT", "0"),
+        ];
+        let t: Result<TestRadix, _> = from_iter(vars);
+        assert!(t.is_err());
+
+        assert!(parse_int::<u8>("0x100").is_err());
+        assert!(parse_int::<i8>("0x-1").is_err());
+        assert!(parse_int::<u8>("0x").is_err());
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestUnderscore {
+        max: u32,
+        ratio: f64,
+        mask: u16,
+        name: String,
+    }
+
+    #[test]
+    fn test_from_iter_underscore() {
+        let vars = [
+            ("MAX", "1_000"),
+            ("RATIO", "3_000.5"),
+            ("MASK", "0xFF_FF"),
+            ("NAME", "1_000"),
+        ];
+
+        let t: TestUnderscore = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestUnderscore {
+                max: 1000,
+                ratio: 3000.5,
+                mask: 0xFFFF,
+                name: "1_000".to_string(),
+            }
+        );
+
+        let t: serde_json::Value =
+            from_iter([("A", "1_000"), ("B", "-2_0"), ("C", "a_b")]).expect("must success");
+        assert_eq!(t, serde_json::json!({ "a": 1000, "b": -20, "c": "a_b" }));
+
+        assert!(parse_int::<u32>("_1").is_err());
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestSpaced {
+        port: u16,
+        offset: i64,
+        ratio: f64,
+        enable: bool,
+        name: String,
+    }
+
+    #[test]
+    fn test_from_iter_spaced_scalars() {
+        let vars = [
+            ("PORT", " 123 "),
+            ("OFFSET", "\t-1\n"),
+            ("RATIO", " 1.5"),
+            ("ENABLE", " true "),
+            ("NAME", " app "),
+        ];
+
+        let t: TestSpaced = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestSpaced {
+                port: 123,
+                offset: -1,
+                ratio: 1.5,
+                enable: true,
+                // Strings are kept as is.
+                name: " app ".to_string(),
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestChar {
+        sep: char,
+        mark: char,
+    }
+
+    #[test]
+    fn test_from_iter_char() {
+        let t: TestChar = from_iter([("SEP", ":"), ("MARK", "🎉")]).expect("must success");
+        assert_eq!(
+            t,
+            TestChar {
+                sep: ':',
+                mark: '🎉'
+            }
+        );
+
+        let err = from_iter::<_, _, TestChar>([("SEP", "abc"), ("MARK", "x")])
+            .expect_err("sep is invalid");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `sep`: expected a single character, got \"abc\""
+        );
+
+        let err = from_iter::<_, _, TestChar>([("SEP", ":"), ("MARK", "🎉🎉")])
+            .expect_err("mark is invalid");
+        assert!(err.to_string().contains("`mark`"), "{err}");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFilteredApp {
+        app: TestFilteredInner,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestFilteredInner {
+        name: String,
+        secret: Option<String>,
+    }
+
+    #[test]
+    fn test_from_env_filtered() {
+        temp_env::with_vars(
+            [
+                ("APP_NAME", Some("app")),
+                ("APP_SECRET", Some("hunter2")),
+                ("OTHER_NAME", Some("other")),
+            ],
+            || {
+                let t: TestFilteredApp =
+                    from_env_filtered(|k| !k.contains("SECRET")).expect("must success");
+                assert_eq!(t.app.name, "app");
+                assert_eq!(t.app.secret, None);
+
+                let t: HashMap<String, String> =
+                    from_env_filtered(|k| k.starts_with("APP_")).expect("must success");
+                assert_eq!(t["app_name"], "app");
+                assert_eq!(t["app_secret"], "hunter2");
+                assert!(t.keys().all(|k| k.starts_with("app")));
+            },
+        )
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestKeyMap {
+        db: TestKeyMapDb,
+        log_level: Option<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestKeyMapDb {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_from_iter_with_key_map() {
+        let vars = [
+            ("MYAPP__DB__HOST", "localhost"),
+            ("MYAPP__DB__PORT", "5432"),
+            ("MYAPP__LOG_LEVEL", "debug"),
+            ("DB_HOST", "ignored"),
+        ];
+
+        let t: TestKeyMap = from_iter_with_key_map(vars, |k| {
+            k.strip_prefix("MYAPP__").map(|k| k.replace("__", "_"))
+        })
+        .expect("must success");
+        assert_eq!(
+            t,
+            TestKeyMap {
+                db: TestKeyMapDb {
+                    host: "localhost".to_string(),
+                    port: 5432,
+                },
+                log_level: Some("debug".to_string()),
+            }
+        );
+
+        // Drop a key by returning `None`.
+        let t: TestKeyMap = from_iter_with_key_map(vars, |k| {
+            let k = k.strip_prefix("MYAPP__")?;
+            (k != "LOG_LEVEL").then(|| k.replace("__", "_"))
+        })
+        .expect("must success");
+        assert_eq!(t.log_level, None);
+        assert_eq!(t.db.port, 5432);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestArray {
+        rgb: [u8; 3],
+    }
+
+    #[test]
+    fn test_from_iter_array_length() {
+        let t: TestArray = from_iter([("RGB", "255,128,0")]).expect("must success");
+        assert_eq!(t.rgb, [255, 128, 0]);
+
+        let err = from_iter::<_, _, TestArray>([("RGB", "255,128")]).expect_err("too few");
+        assert!(
+            err.to_string()
+                .contains("invalid length 2, expected 3 elements separated by `,`"),
+            "{err}"
+        );
+
+        let err = from_iter::<_, _, TestArray>([("RGB", "255,128,0,1")]).expect_err("too many");
+        assert!(
+            err.to_string()
+                .contains("invalid length 4, expected 3 elements separated by `,`"),
+            "{err}"
+        );
+
+        let de = EnvDeserializer::new().list_delimiter(';');
+        let t: TestArray = de.from_iter([("RGB", "255;128;0")]).expect("must success");
+        assert_eq!(t.rgb, [255, 128, 0]);
+
+        let err = de
+            .from_iter::<_, _, TestArray>([("RGB", "255;128")])
+            .expect_err("too few");
+        assert!(
+            err.to_string()
+                .contains("invalid length 2, expected 3 elements separated by `;`"),
+            "{err}"
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDenyUnknown {
+        name: String,
+        db: TestDenyUnknownDb,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct TestDenyUnknownDb {
+        host: String,
+        pool_size: Option<u32>,
+    }
+
+    #[test]
+    fn test_from_iter_deny_unknown_fields() {
+        let vars = [
+            ("NAME", "app"),
+            ("DB_HOST", "localhost"),
+            ("DB_POOL_SIZE", "4"),
+        ];
+        let t: TestDenyUnknown = from_iter(vars).expect("must success");
+        assert_eq!(t.db.host, "localhost");
+        assert_eq!(t.db.pool_size, Some(4));
+
+        let vars = [
+            ("NAME", "app"),
+            ("DB_HOST", "localhost"),
+            ("DB_PORT", "5432"),
+        ];
+        let err = from_iter::<_, _, TestDenyUnknown>(vars).expect_err("port is unknown");
+        assert!(err.to_string().contains("unknown field `port`"), "{err}");
+
+        // Unknown fields are ignored without `deny_unknown_fields`.
+        let vars = [("NAME", "app"), ("DB_HOST", "localhost"), ("OTHER", "x")];
+        let t: TestDenyUnknown = from_iter(vars).expect("must success");
+        assert_eq!(t.name, "app");
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestIgnored {
+        name: String,
+        #[serde(skip_deserializing)]
+        skipped: u32,
+        ignored: de::IgnoredAny,
+    }
+
+    #[test]
+    fn test_from_iter_ignored_any() {
+        let vars = [
+            ("NAME", "app"),
+            ("SKIPPED", "not a number"),
+            ("IGNORED", "1,2;3"),
+            ("IGNORED_A_B", "x"),
+            ("UNKNOWN", "'broken"),
+        ];
+
+        let t: TestIgnored = EnvDeserializer::new()
+            .detect_collisions(true)
+            .group_delimiter(';')
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t.name, "app");
+        assert_eq!(t.skipped, 0);
+    }
+
+    #[test]
+    fn test_from_map() {
+        let map = HashMap::from([
+            ("A".to_string(), "123".to_string()),
+            ("B".to_string(), "true".to_string()),
+            ("C".to_string(), "Hello, test".to_string()),
+            ("D_AA".to_string(), "1.2".to_string()),
+            ("D_BB".to_string(), "Hello".to_string()),
+        ]);
+
+        let t: TestStruct = from_map(map.clone()).expect("must success");
+        assert_eq!(t.d.aa, 1.2);
+        assert_eq!(t.d.bb, "Hello");
+
+        let map = map
+            .into_iter()
+            .map(|(k, v)| (format!("APP_{k}"), v))
+            .collect();
+        let t: TestStruct = from_map_with_prefix(map, "APP").expect("must success");
+        assert_eq!(t.a, 123);
+        assert_eq!(t.d.aa, 1.2);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestPlusSign {
+        a: i32,
+        b: u8,
+    }
+
+    #[test]
+    fn test_from_iter_plus_sign() {
+        let t: TestPlusSign = from_iter([("A", "+42"), ("B", "+0x1F")]).expect("must success");
+        assert_eq!(t, TestPlusSign { a: 42, b: 0x1F });
+
+        let err = from_iter::<_, _, TestPlusSign>([("A", "+"), ("B", "1")]).expect_err("lone +");
+        assert!(err.to_string().contains("`a`"), "{err}");
+
+        let t: serde_json::Value = from_iter([("A", "+42"), ("B", "+")]).expect("must success");
+        assert_eq!(t, serde_json::json!({ "a": 42, "b": "+" }));
+    }
+
+    #[derive(Deserialize, Default, Debug, PartialEq)]
+    struct TestRequiredEmbed {
+        aa: String,
+        bb: u32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDefaultEmbed {
+        a: i64,
+        #[serde(default)]
+        d: TestRequiredEmbed,
+    }
+
+    #[test]
+    fn test_from_iter_default_embed() {
+        let t: TestDefaultEmbed = from_iter([("A", "1"), ("DD", "x")]).expect("must success");
+        assert_eq!(t.d, TestRequiredEmbed::default());
+
+        let t: TestDefaultEmbed =
+            from_iter([("A", "1"), ("D_AA", "x"), ("D_BB", "2")]).expect("must success");
+        assert_eq!(t.d.aa, "x");
+        assert_eq!(t.d.bb, 2);
+
+        // The default only applies when the whole subtree is absent.
+        let err = from_iter::<_, _, TestDefaultEmbed>([("A", "1"), ("D_AA", "x")])
+            .expect_err("bb is missing");
+        assert!(err.to_string().contains("`bb`"), "{err}");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestTags {
+        tag: Vec<String>,
+    }
+
+    #[test]
+    fn test_from_iter_numeric_suffix() {
+        let t: TestTags =
+            from_iter([("TAG_2", "b"), ("TAG_10", "c"), ("TAG_1", "a")]).expect("must success");
+        assert_eq!(t.tag, vec!["a", "b", "c"]);
+
+        let t: TestTags = from_iter([("TAG", "a,b")]).expect("must success");
+        assert_eq!(t.tag, vec!["a", "b"]);
+
+        let t: TestTags =
+            from_iter([("TAG", "x,y"), ("TAG_1", "a"), ("TAG_2", "b")]).expect("must success");
+        assert_eq!(t.tag, vec!["a", "b"]);
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestValidate {
+        name: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_validate_env() {
+        temp_env::with_vars(vec![("NAME", Some("app")), ("PORT", Some("80"))], || {
+            validate_env::<TestValidate>().expect("must success")
+        });
 
-    #[derive(Deserialize, Default, PartialEq, Debug)]
-    #[serde(default)]
-    struct TestStruct {
-        a: i64,
-        b: bool,
-        c: String,
-        d: EmbedStruct,
+        temp_env::with_vars(vec![("NAME", Some("app")), ("PORT", Some("http"))], || {
+            let err = validate_env::<TestValidate>().expect_err("port is invalid");
+            assert!(
+                err.to_string()
+                    .starts_with("invalid value for field `port`"),
+                "{err}"
+            );
+        });
     }
 
-    #[derive(Deserialize, Default, PartialEq, Debug)]
-    #[serde(default)]
-    struct EmbedStruct {
-        aa: f32,
-        bb: String,
+    #[derive(Deserialize, Debug)]
+    struct TestProfile {
+        userprofile: String,
     }
 
     #[test]
-    fn test_from_env() {
+    fn test_from_iter_key_case() {
+        for key in ["USERPROFILE", "UserProfile", "userprofile"] {
+            let t: TestProfile = from_iter([(key, "C:\\Users\\app")]).expect("must success");
+            assert_eq!(t.userprofile, "C:\\Users\\app");
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_from_env_userprofile() {
+        let t: TestProfile = from_env().expect("must success");
+        assert_eq!(t.userprofile, std::env::var("USERPROFILE").unwrap());
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestCow {
+        name: Cow<'static, str>,
+        tags: Vec<Cow<'static, str>>,
+    }
+
+    #[test]
+    fn test_from_iter_cow() {
+        let vars = [("NAME", "app"), ("TAGS", "a,b")];
+        let t: TestCow = from_iter(vars).expect("must success");
+        assert_eq!(t.name, "app");
+        assert_eq!(t.tags, ["a", "b"]);
+        assert!(matches!(t.name, Cow::Owned(_)));
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestNestedScalar {
+        a: String,
+        d: String,
+    }
+
+    #[test]
+    fn test_from_iter_nested_scalar() {
+        let vars = [("A", "x"), ("D_AA", "1"), ("D_BB_C", "2")];
+        let err = from_iter::<_, _, TestNestedScalar>(vars).expect_err("d is nested");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `d`: expected a scalar but found nested keys D_AA, D_BB_C"
+        );
+
+        // Maps of strings read flat keys instead.
+        let t: HashMap<String, String> = from_iter(vars).expect("must success");
+        assert_eq!(t.get("d"), None);
+        assert_eq!(t["d_aa"], "1");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestNet {
+        bind: std::net::SocketAddr,
+        ip: std::net::IpAddr,
+        ipv6: std::net::Ipv6Addr,
+        peers: Vec<std::net::SocketAddr>,
+    }
+
+    #[test]
+    fn test_from_iter_net() {
+        let vars = [
+            ("BIND", "0.0.0.0:8080"),
+            ("IP", "::1"),
+            ("IPV6", "fe80::1"),
+            ("PEERS", "[::1]:8080, 127.0.0.1:80"),
+        ];
+
+        let t: TestNet = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            TestNet {
+                bind: "0.0.0.0:8080".parse().unwrap(),
+                ip: "::1".parse().unwrap(),
+                ipv6: "fe80::1".parse().unwrap(),
+                peers: vec![
+                    "[::1]:8080".parse().unwrap(),
+                    "127.0.0.1:80".parse().unwrap()
+                ],
+            }
+        );
+
+        let err = from_iter::<_, _, TestNet>([("BIND", "::1:8080")]).expect_err("must fail");
+        assert!(err.to_string().contains("socket address"), "{err}");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestOrDefault {
+        host: String,
+        port: Option<u16>,
+    }
+
+    impl Default for TestOrDefault {
+        fn default() -> Self {
+            Self {
+                host: "localhost".to_string(),
+                port: Some(8080),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_env_or_default() {
+        temp_env::with_vars(vec![("OR_DEFAULT_APP_HOST", Some("example.com"))], || {
+            let t: TestOrDefault = from_env_with_prefix_or_default("OR_DEFAULT_APP");
+            assert_eq!(t.host, "example.com");
+            assert_eq!(t.port, None);
+        });
+
         temp_env::with_vars(
             vec![
-                ("A", Some("123")),
-                ("B", Some("true")),
-                ("C", Some("Hello, test")),
-                ("D_AA", Some("1.2")),
-                ("D_BB", Some("Hello, embed")),
+                ("OR_DEFAULT_APP_HOST", Some("example.com")),
+                ("OR_DEFAULT_APP_PORT", Some("http")),
             ],
             || {
-                let t: TestStruct = from_env().expect("must success");
-                assert_eq!(
-                    t,
-                    TestStruct {
-                        a: 123,
-                        b: true,
-                        c: "Hello, test".to_string(),
-                        d: EmbedStruct {
-                            aa: 1.2,
-                            bb: "Hello, embed".to_string()
-                        }
-                    }
-                )
+                let t: TestOrDefault = from_env_with_prefix_or_default("OR_DEFAULT_APP");
+                assert_eq!(t, TestOrDefault::default());
             },
-        )
-    }
+        );
 
-    /// This test is ported from [softprops/envy](https://github.com/softprops/envy/blob/801d81e7c3e443470e110bf4e34460acba113476/src/lib.rs#L410)
-    #[derive(Deserialize, Debug, PartialEq, Eq)]
-    struct Foo {
-        bar: String,
-        baz: bool,
-        zoom: Option<u16>,
-        doom: Vec<u64>,
-        boom: Vec<String>,
-        #[serde(default = "default_kaboom")]
-        kaboom: u16,
-        #[serde(default)]
-        debug_mode: bool,
-        provided: Option<String>,
-        newtype: CustomNewType,
-        boom_zoom: bool,
-        #[serde(default = "default_bool")]
-        mode_xx: bool,
-    }
+        temp_env::with_vars(vec![("OR_DEFAULT_APP_HOST", None::<&str>)], || {
+            let t: TestOrDefault = from_env_with_prefix_or_default("OR_DEFAULT_APP");
+            assert_eq!(t, TestOrDefault::default());
+        });
 
-    fn default_bool() -> bool {
-        true
+        temp_env::with_vars(
+            vec![("HOST", Some("example.com")), ("PORT", Some("80"))],
+            || {
+                let t: TestOrDefault = from_env_or_default();
+                assert_eq!(t.host, "example.com");
+                assert_eq!(t.port, Some(80));
+            },
+        );
     }
 
-    fn default_kaboom() -> u16 {
-        8080
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestTracked {
+        tracked_name: String,
+        tracked_db: TestTrackedDb,
+        #[serde(default)]
+        tracked_missing: String,
     }
 
-    #[derive(Deserialize, Debug, PartialEq, Eq, Default)]
-    struct CustomNewType(u32);
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct TestTrackedDb {
+        host: String,
+        port: u16,
+    }
 
     #[test]
-    fn test_ported_from_envy() {
+    fn test_from_env_tracked() {
         temp_env::with_vars(
             vec![
-                ("BAR", Some("test")),
-                ("BAZ", Some("true")),
-                ("DOOM", Some("1, 2, 3 ")),
-                // Empty string should result in empty vector.
-                ("BOOM", Some("")),
-                ("SIZE", Some("small")),
-                ("PROVIDED", Some("test")),
-                ("NEWTYPE", Some("42")),
-                ("boom_zoom", Some("true")),
-                ("mode_xx", Some("false")),
+                ("TRACKED_NAME", Some("app")),
+                ("TRACKED_DB_HOST", Some("localhost")),
+                ("TRACKED_DB_PORT", Some("5432")),
+                ("TRACKED_OTHER", Some("x")),
             ],
             || {
-                let actual: Foo = from_env().expect("must success");
-                assert_eq!(
-                    actual,
-                    Foo {
-                        bar: String::from("test"),
-                        baz: true,
-                        zoom: None,
-                        doom: vec![1, 2, 3],
-                        boom: vec![],
-                        kaboom: 8080,
-                        debug_mode: false,
-                        provided: Some(String::from("test")),
-                        newtype: CustomNewType(42),
-                        boom_zoom: true,
-                        mode_xx: false
-                    }
-                )
+                let (t, keys) = from_env_tracked::<TestTracked>().expect("must success");
+                assert_eq!(t.tracked_db.port, 5432);
+                assert_eq!(keys, ["TRACKED_NAME", "TRACKED_DB_HOST", "TRACKED_DB_PORT"]);
             },
-        )
+        );
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct TestStructAlias {
-        #[serde(alias = "meta_log_level")]
-        log_level: String,
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDbConfig {
+        host: String,
+        port: Option<u16>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDbMap {
+        db: HashMap<String, TestDbConfig>,
     }
 
-    // We are not support alias now.
     #[test]
-    #[ignore]
-    fn test_from_env_alias() {
-        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
-            let t: TestStructAlias = from_env().expect("must success");
-            assert_eq!(
-                t,
-                TestStructAlias {
-                    log_level: "DEBUG".to_string()
-                }
-            )
-        })
+    fn test_from_iter_map_of_structs() {
+        let vars = [
+            ("DB_PRIMARY_HOST", "a.example"),
+            ("DB_PRIMARY_PORT", "5432"),
+            ("DB_REPLICA_HOST", "b.example"),
+        ];
+
+        let t: TestDbMap = from_iter(vars).expect("must success");
+        assert_eq!(t.db.len(), 2);
+        assert_eq!(
+            t.db["primary"],
+            TestDbConfig {
+                host: "a.example".to_string(),
+                port: Some(5432),
+            }
+        );
+        assert_eq!(t.db["replica"].host, "b.example");
+        assert_eq!(t.db["replica"].port, None);
+
+        let t: HashMap<String, Vec<u32>> =
+            from_iter([("A_0", "1"), ("A_1", "2"), ("B", "3,4")]).expect("must success");
+        assert_eq!(
+            t,
+            HashMap::from([("a".into(), vec![1, 2]), ("b".into(), vec![3, 4])])
+        );
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct TestStructFlat {
-        meta_log_level: String,
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestIndexedOrder {
+        entries: Vec<TestIndexedEntry>,
+        names: BTreeMap<u32, String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestIndexedEntry {
+        id: u32,
     }
 
     #[test]
-    fn test_from_env_flat() {
-        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
-            let t: TestStructFlat = from_env().expect("must success");
-            assert_eq!(
-                t,
-                TestStructFlat {
-                    meta_log_level: "DEBUG".to_string()
+    fn test_from_iter_indexed_order() {
+        let indices = [11, 2, 10, 0, 1];
+        let vars: Vec<_> = indices
+            .iter()
+            .flat_map(|i| {
+                [
+                    (format!("ENTRIES_{i}_ID"), i.to_string()),
+                    (format!("NAMES_{i}"), format!("n{i}")),
+                ]
+            })
+            .collect();
+
+        let t: TestIndexedOrder = from_iter(vars.clone()).expect("must success");
+        let ids: Vec<_> = t.entries.iter().map(|v| v.id).collect();
+        assert_eq!(ids, [0, 1, 2, 10, 11]);
+        let keys: Vec<_> = t.names.keys().copied().collect();
+        assert_eq!(keys, [0, 1, 2, 10, 11]);
+
+        // Maps visit numeric keys in numeric order too.
+        struct Keys(Vec<String>);
+
+        impl<'de> Deserialize<'de> for Keys {
+            fn deserialize<D: de::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                struct KeysVisitor;
+
+                impl<'de> Visitor<'de> for KeysVisitor {
+                    type Value = Keys;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("a map")
+                    }
+
+                    fn visit_map<A: de::MapAccess<'de>>(
+                        self,
+                        mut map: A,
+                    ) -> Result<Keys, A::Error> {
+                        let mut keys = Vec::new();
+                        while let Some((k, _)) = map.next_entry::<String, de::IgnoredAny>()? {
+                            keys.push(k);
+                        }
+                        Ok(Keys(keys))
+                    }
                 }
-            )
-        })
+
+                d.deserialize_any(KeysVisitor)
+            }
+        }
+
+        let vars: Vec<_> = indices
+            .iter()
+            .map(|i| (i.to_string(), i.to_string()))
+            .collect();
+        let t: Keys = from_iter(vars).expect("must success");
+        assert_eq!(t.0, ["0", "1", "2", "10", "11"]);
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestFlattenMetrics {
+        name: String,
+        #[serde(flatten)]
+        metrics: HashMap<String, f64>,
+    }
+
+    #[test]
+    fn test_from_iter_flatten_typed_map() {
+        let vars = [("NAME", "app"), ("CPU", "3"), ("MEM", "1.5")];
+        let t: TestFlattenMetrics = from_iter(vars).expect("must success");
+        assert_eq!(
+            t.metrics,
+            HashMap::from([("cpu".to_string(), 3.0), ("mem".to_string(), 1.5)])
+        );
+
+        let de = EnvDeserializer::new().infer_float(true);
+        let t: TestFlattenMetrics = de.from_iter(vars).expect("must success");
+        assert_eq!(t.name, "app");
+        assert_eq!(
+            t.metrics,
+            HashMap::from([("cpu".to_string(), 3.0), ("mem".to_string(), 1.5)])
+        );
+
+        let err = de
+            .from_iter::<_, _, TestFlattenMetrics>([("NAME", "app"), ("CPU", "high")])
+            .expect_err("must fail");
+        assert!(err.to_string().contains("expected f64"), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_field() {
+        let node: Node = [("PORT", "8080"), ("DB_URL", "abc"), ("DB_SIZE", "x")]
+            .into_iter()
+            .collect();
+
+        let port: u32 = deserialize_field(&node, "port").expect("must success");
+        assert_eq!(port, 8080);
+        let url: &str = deserialize_field(&node, "db_url").expect("must success");
+        assert_eq!(url, "abc");
+        let host: Option<String> = deserialize_field(&node, "db_host").expect("must success");
+        assert_eq!(host, None);
+
+        let err = deserialize_field::<u32>(&node, "db_size").expect_err("size is invalid");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `db_size`: invalid digit found in string"
+        );
+
+        let err = deserialize_field::<u32>(&node, "timeout").expect_err("timeout is missing");
+        match err.kind() {
+            ErrorKind::MissingField(v) => assert_eq!(v.key(), "TIMEOUT"),
+            v => panic!("unexpected kind: {v:?}"),
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestFlattenRest {
+        name: String,
+        db: TestDbPool,
+        #[serde(flatten)]
+        rest: HashMap<String, String>,
+    }
+
+    #[test]
+    fn test_from_iter_flatten_named_fields() {
+        let t: TestFlattenRest =
+            from_iter([("NAME", "app"), ("DB_SIZE", "8"), ("LOG", "info")]).expect("must success");
+        assert_eq!(t.name, "app");
+        assert_eq!(t.db.size, 8);
+        assert_eq!(
+            t.rest,
+            HashMap::from([("log".to_string(), "info".to_string())])
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDbPrefix {
+        url: String,
+        pool: TestDbPool,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDbPool {
+        size: u32,
+    }
+
+    #[test]
+    fn test_from_env_with_multi_segment_prefix() {
+        let expected = TestDbPrefix {
+            url: "postgres://db".to_string(),
+            pool: TestDbPool { size: 8 },
+        };
+        let vars = [
+            ("APP_DB_URL", "postgres://db"),
+            ("APP_DB_POOL_SIZE", "8"),
+            ("APP_DBX_URL", "other"),
+            ("APP_URL", "other"),
+        ];
+
+        let t: TestDbPrefix = from_iter_with_prefix(vars, "APP_DB").expect("must success");
+        assert_eq!(t, expected);
+
+        temp_env::with_vars(vars.map(|(k, v)| (format!("MULTI_{k}"), Some(v))), || {
+            let t: TestDbPrefix = from_env_with_prefix("MULTI_APP_DB").expect("must success");
+            assert_eq!(t, expected);
+        });
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("serde-env-{}.env", std::process::id()));
+        std::fs::write(&path, "# db\nDB_HOST=localhost\nDB_PORT=5432\n\nDB_NAME\n")
+            .expect("write temp file");
+
+        let err = from_file::<TestDbMap>(&path).expect_err("line 5 is broken");
+        assert_eq!(
+            err.to_string(),
+            format!("invalid syntax at {}:5: expected KEY=VALUE", path.display())
+        );
+
+        std::fs::write(&path, "# db\nDB_PRIMARY_HOST=localhost\n").expect("write temp file");
+        let t: TestDbMap = from_file(&path).expect("must success");
+        assert_eq!(t.db["primary"].host, "localhost");
+        std::fs::remove_file(&path).expect("remove temp file");
+
+        let err = from_file::<TestDbMap>(&path).expect_err("file is removed");
+        assert!(matches!(err.kind(), crate::ErrorKind::Io { .. }), "{err}");
+        let source = std::error::Error::source(&err).expect("source must exist");
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().map(|v| v.kind()),
+            Some(std::io::ErrorKind::NotFound)
+        );
+        assert!(
+            err.to_string()
+                .starts_with(&format!("read {}: ", path.display())),
+            "{err}"
+        );
+
+        let err = from_reader::<_, TestDbMap>(&[0xff][..]).expect_err("invalid utf-8");
+        assert!(err.to_string().starts_with("read <reader>: "), "{err}");
+        let source = std::error::Error::source(&err).expect("source must exist");
+        assert!(source.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn test_int128() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            big: u128,
+            neg: i128,
+            hex: u128,
+        }
+
+        let t: Test = from_iter([
+            ("BIG", "340282366920938463463374607431768211455"),
+            ("NEG", "-18446744073709551616"),
+            ("HEX", "0xFFFF_FFFF_FFFF_FFFF_FF"),
+        ])
+        .expect("must success");
+        assert_eq!(
+            t,
+            Test {
+                big: u128::MAX,
+                neg: -(u64::MAX as i128) - 1,
+                hex: (u64::MAX as u128) << 8 | 0xFF,
+            }
+        );
+
+        let err = from_iter::<_, _, Test>([("BIG", "abc"), ("NEG", "1"), ("HEX", "1")])
+            .expect_err("big is invalid");
+        assert!(err.to_string().contains("`big`"), "{err}");
+
+        // Untyped values above 64 bits are kept as strings, so that they
+        // still work with flatten.
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Flatten {
+            #[serde(flatten)]
+            rest: HashMap<String, String>,
+        }
+
+        let t: Flatten = from_iter([("ID", "18446744073709551616")]).expect("must success");
+        assert_eq!(t.rest["id"], "18446744073709551616");
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Keys {
+            ids: HashMap<u128, String>,
+        }
+
+        let t: Keys = from_iter([("IDS_18446744073709551616", "a")]).expect("must success");
+        assert_eq!(
+            t.ids,
+            HashMap::from([(u64::MAX as u128 + 1, "a".to_string())])
+        );
     }
 
     #[test]
-    fn test_from_env_flat_upper() {
-        temp_env::with_vars(vec![("META_LOG_LEVEL", Some("DEBUG"))], || {
-            let t: TestStructFlat = from_env().expect("must success");
-            assert_eq!(
-                t,
-                TestStructFlat {
-                    meta_log_level: "DEBUG".to_string()
-                }
-            )
-        })
-    }
+    fn test_nonzero() {
+        use std::num::{NonZeroI32, NonZeroU16};
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct TestStructFlatWithDefault {
-        meta_log_level: String,
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            port: NonZeroU16,
+            offset: Option<NonZeroI32>,
+        }
+
+        let t: Test = from_iter([("PORT", "8080"), ("OFFSET", "-1")]).expect("must success");
+        assert_eq!(t.port.get(), 8080);
+        assert_eq!(t.offset.map(NonZeroI32::get), Some(-1));
+
+        let err = from_iter::<_, _, Test>([("PORT", "0")]).expect_err("port is zero");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `port`: expected a non-zero value"
+        );
+
+        let err = from_iter::<_, _, Test>([("PORT", "1"), ("OFFSET", "x")]).expect_err("not int");
+        assert!(err.to_string().contains("invalid digit"), "{err}");
+
+        let node: Node = [("COUNT", "0")].into_iter().collect();
+        let count: u16 = deserialize_field(&node, "count").expect("zero is fine");
+        assert_eq!(count, 0);
     }
 
-    impl Default for TestStructFlatWithDefault {
-        fn default() -> Self {
-            Self {
-                meta_log_level: "INFO".to_string(),
+    #[test]
+    fn test_infer_int128() {
+        // A hand-written visitor, since untagged enums buffer values and
+        // can't take 128-bit integers.
+        #[derive(Debug, PartialEq)]
+        enum Value {
+            U64(u64),
+            U128(u128),
+            I128(i128),
+            Str(String),
+        }
+
+        impl<'de> Deserialize<'de> for Value {
+            fn deserialize<D: de::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                struct ValueVisitor;
+
+                impl Visitor<'_> for ValueVisitor {
+                    type Value = Value;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("any value")
+                    }
+
+                    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+                        Ok(Value::U64(v))
+                    }
+
+                    fn visit_u128<E>(self, v: u128) -> Result<Value, E> {
+                        Ok(Value::U128(v))
+                    }
+
+                    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+                        Ok(Value::I128(v))
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+                        Ok(Value::Str(v.to_string()))
+                    }
+                }
+
+                d.deserialize_any(ValueVisitor)
             }
         }
+
+        let vars = [
+            ("A", "42"),
+            ("B", "18446744073709551616"),
+            ("C", "-18446744073709551616"),
+            ("D", "999999999999999999999999999999999999999999"),
+        ];
+        let t: HashMap<String, Value> = EnvDeserializer::new()
+            .infer_int128(true)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(t["a"], Value::U64(42));
+        assert_eq!(t["b"], Value::U128(u64::MAX as u128 + 1));
+        assert_eq!(t["c"], Value::I128(-(u64::MAX as i128) - 1));
+        assert_eq!(
+            t["d"],
+            Value::Str("999999999999999999999999999999999999999999".to_string())
+        );
+
+        // Beyond 64 bits for `serde_json::Value`.
+        let vars = [("B", "18446744073709551616")];
+        let err = EnvDeserializer::new()
+            .infer_int128(true)
+            .from_iter::<_, _, HashMap<String, serde_json::Value>>(vars)
+            .expect_err("must fail");
+        assert!(err.to_string().contains("out of range"), "{err}");
+
+        // Flatten buffers them as strings, and captured nodes keep them as
+        // is.
+        let vars = [
+            ("NAME", "app"),
+            ("B", "18446744073709551616"),
+            ("C", "-18446744073709551616"),
+        ];
+        let t: TestRawNode = EnvDeserializer::new()
+            .infer_int128(true)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t.raw.get("b").map(Node::value),
+            Some("18446744073709551616")
+        );
+        assert_eq!(
+            t.raw.get("c").map(Node::value),
+            Some("-18446744073709551616")
+        );
     }
 
     #[test]
-    fn test_from_env_flat_with_default() {
-        temp_env::with_vars(vec![("meta_log_level", Some("DEBUG"))], || {
-            let t: TestStructFlatWithDefault = from_env().expect("must success");
-            assert_eq!(
-                t,
-                TestStructFlatWithDefault {
-                    meta_log_level: "DEBUG".to_string()
-                }
-            )
-        })
+    fn test_option_seq_present_empty() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            foo3: Option<Vec<u32>>,
+        }
+
+        // Absent, present but empty and present with values.
+        let cases = |de: EnvDeserializer| -> [Option<Vec<u32>>; 3] {
+            [vec![], vec![("FOO3", "")], vec![("FOO3", "1,2")]]
+                .map(|vars| de.from_iter::<_, _, Test>(vars).expect("must success").foo3)
+        };
+
+        let de = EnvDeserializer::new().empty_string_is_none(false);
+        assert_eq!(cases(de), [None, Some(vec![]), Some(vec![1, 2])]);
+
+        // Empty is the same as absent by default.
+        assert_eq!(
+            cases(EnvDeserializer::new()),
+            [None, None, Some(vec![1, 2])]
+        );
     }
 
     #[test]
-    fn test_from_env_flat_upper_with_default() {
-        temp_env::with_vars(vec![("META_LOG_LEVEL", Some("DEBUG"))], || {
-            let t: TestStructFlatWithDefault = from_env().expect("must success");
-            assert_eq!(
-                t,
-                TestStructFlatWithDefault {
-                    meta_log_level: "DEBUG".to_string()
-                }
-            )
-        })
+    fn test_map_of_options() {
+        let vars = [("A", ""), ("B", "x"), ("C_D", "")];
+
+        // Empty values are unset, so they are not in the map at all.
+        let t: HashMap<String, Option<String>> = from_iter(vars).expect("must success");
+        assert_eq!(t, HashMap::from([("b".to_string(), Some("x".to_string()))]));
+
+        let t: HashMap<String, Option<String>> = EnvDeserializer::new()
+            .empty_string_is_none(false)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            HashMap::from([
+                ("a".to_string(), Some(String::new())),
+                ("b".to_string(), Some("x".to_string())),
+                ("c_d".to_string(), Some(String::new())),
+            ])
+        );
     }
 
-    #[test]
-    fn test_from_env_as_map() {
-        temp_env::with_vars(vec![("METASRV_LOG_LEVEL", Some("DEBUG"))], || {
-            let t: HashMap<String, String> = from_env().expect("must success");
-            assert_eq!(t["metasrv_log_level"], "DEBUG".to_string())
-        })
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDeep {
+        server: TestDeepServer,
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct EnumNewtype {
-        bar: String,
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDeepServer {
+        http: TestDeepHttp,
+        max_conn: u32,
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct ExternallyEnumStruct {
-        foo: ExternallyEnum,
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDeepHttp {
+        port: u16,
+        tls: TestDeepTls,
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    enum ExternallyEnum {
-        X,
-        Y(EnumNewtype),
-        Z { a: i32 },
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestDeepTls {
+        cert_path: String,
+        enabled: bool,
     }
 
     #[test]
-    fn test_from_env_externally_enum() {
-        temp_env::with_vars(vec![("FOO", Some("X"))], || {
-            let t: ExternallyEnumStruct = from_env().expect("must success");
-            assert_eq!(t.foo, ExternallyEnum::X)
-        });
-
-        temp_env::with_vars(vec![("FOO", Some("Y")), ("FOO_BAR", Some("xxx"))], || {
-            let t: ExternallyEnumStruct = from_env().expect("must success");
-            assert_eq!(
-                t.foo,
-                ExternallyEnum::Y(EnumNewtype {
-                    bar: "xxx".to_string()
-                })
-            )
-        });
+    fn test_from_iter_four_levels() {
+        let vars = [
+            ("APP_SERVER_MAX_CONN", "64"),
+            ("APP_SERVER_HTTP_PORT", "8080"),
+            ("APP_SERVER_HTTP_TLS_CERT_PATH", "/etc/cert.pem"),
+            ("APP_SERVER_HTTP_TLS_ENABLED", "true"),
+            ("APP_SERVER_HTTP_TLS_UNKNOWN_KEY", "ignored"),
+        ];
+        let t: TestDeep = from_iter_with_prefix(vars, "APP").expect("must success");
+        assert_eq!(
+            t,
+            TestDeep {
+                server: TestDeepServer {
+                    http: TestDeepHttp {
+                        port: 8080,
+                        tls: TestDeepTls {
+                            cert_path: "/etc/cert.pem".to_string(),
+                            enabled: true,
+                        },
+                    },
+                    max_conn: 64,
+                },
+            }
+        );
 
-        temp_env::with_vars(vec![("FOO", Some("Z")), ("FOO_A", Some("1"))], || {
-            let t: ExternallyEnumStruct = from_env().expect("must success");
-            assert_eq!(t.foo, ExternallyEnum::Z { a: 1 })
-        });
+        let err = from_iter_with_prefix::<_, _, TestDeep>(vars[..3].iter().copied(), "APP")
+            .expect_err("enabled is missing");
+        assert!(err.to_string().contains("enabled"), "{err}");
     }
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    struct InternallyEnumStruct {
-        foo: InternallyEnum,
-    }
+    #[test]
+    fn test_from_iter_deep_keys() {
+        // Keys are split into one node per segment, so depth is only
+        // limited by the key length.
+        let key = vec!["A"; 256].join("_");
+        let node: Node = [(key.as_str(), "1")].into_iter().collect();
+        let path = vec!["a"; 256].join("_");
+        assert_eq!(node.get(&path).map(Node::value), Some("1"));
+        assert_eq!(node.flatten().last(), Some(&path));
 
-    #[derive(Deserialize, PartialEq, Debug)]
-    #[serde(tag = "type")]
-    enum InternallyEnum {
-        X,
-        Y(EnumNewtype),
-        Z { a: i32 },
+        let t: HashMap<String, String> = from_node(&node).expect("must success");
+        assert_eq!(t[&path], "1");
     }
 
-    // Currently Internally / Adjacently / Untagged enum is not support by the following issues
-    // https://github.com/serde-rs/serde/issues/2187
     #[test]
-    #[ignore]
-    fn test_from_env_internally_enum() {
-        temp_env::with_vars(vec![("FOO_TYPE", Some("X"))], || {
-            let t: InternallyEnumStruct = from_env().expect("must success");
-            assert_eq!(t.foo, InternallyEnum::X)
-        });
+    fn test_from_node_merged() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            host: String,
+            port: u16,
+            tags: Vec<String>,
+        }
 
-        temp_env::with_vars(
-            vec![("FOO_TYPE", Some("Y")), ("FOO_BAR", Some("xxx"))],
-            || {
-                let t: InternallyEnumStruct = from_env().expect("must success");
-                assert_eq!(
-                    t.foo,
-                    InternallyEnum::Y(EnumNewtype {
-                        bar: "xxx".to_string()
-                    })
-                )
-            },
+        let mut node: Node = [("HOST", "localhost"), ("TAGS", "a,b")]
+            .into_iter()
+            .collect();
+        node.merge(
+            [("HOST", "example.com"), ("PORT", "80")]
+                .into_iter()
+                .collect(),
         );
 
-        temp_env::with_vars(vec![("FOO_TYPE", Some("Z")), ("FOO_A", Some("1"))], || {
-            let t: InternallyEnumStruct = from_env().expect("must success");
-            assert_eq!(t.foo, InternallyEnum::Z { a: 1 })
-        });
+        let t: Test = from_node(&node).expect("must success");
+        assert_eq!(
+            t,
+            Test {
+                host: "localhost".to_string(),
+                port: 80,
+                tags: vec!["a".to_string(), "b".to_string()],
+            }
+        );
     }
 
-    #[derive(Deserialize, PartialEq, Debug, Eq)]
-    struct DoubleOptionOuter {
-        inner: Option<DoubleOptionInner>,
-    }
+    #[test]
+    fn test_collision_precedence() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Db {
+            host: String,
+        }
 
-    #[derive(Deserialize, PartialEq, Debug, Eq)]
-    struct DoubleOptionInner {
-        val: Option<u8>,
-    }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            db: Db,
+        }
 
-    #[test]
-    fn double_option() {
-        temp_env::with_var("INNER_VAL", Some("2"), || {
-            let t: DoubleOptionOuter = from_env().expect("must success");
-            assert_eq!(
-                t,
-                DoubleOptionOuter {
-                    inner: Some(DoubleOptionInner { val: Some(2) })
-                }
-            )
-        })
-    }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestScalar {
+            db: String,
+        }
 
-    #[derive(Deserialize, Debug, PartialEq)]
-    struct TestFlatten {
-        silent: Option<String>,
-        pub_hosted_url: String,
-        #[serde(rename = "pub")]
-        pub_: TestPub,
-        #[serde(flatten)]
-        inner: TestFlattenInner,
-    }
+        let vars = [("DB", "foo"), ("DB_HOST", "bar")];
 
-    #[derive(Deserialize, Debug, PartialEq)]
-    struct TestPub {
-        hosted: TestPubInner,
+        // Children win for struct targets, and the value wins for scalars.
+        let t: TestStruct = from_iter(vars).expect("must success");
+        assert_eq!(t.db.host, "bar");
+        let t: TestScalar = from_iter(vars).expect("must success");
+        assert_eq!(t.db, "foo");
+
+        let de = EnvDeserializer::new().detect_collisions(true);
+        let err = de
+            .from_iter::<_, _, TestStruct>(vars)
+            .expect_err("db collides");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `db`: both value `foo` and nested keys found, but expected a struct"
+        );
+        let err = de
+            .from_iter::<_, _, TestScalar>(vars)
+            .expect_err("db collides");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `db`: both value `foo` and nested keys found, but expected a scalar"
+        );
     }
+
     #[derive(Deserialize, Debug, PartialEq)]
-    struct TestPubInner {
-        url: String,
+    struct TestDefaults {
+        serde_env_defaults: TestDefaultsInner,
     }
 
     #[derive(Deserialize, Debug, PartialEq)]
-    struct TestFlattenInner {
-        port: u32,
-        enable: bool,
-        t: Option<u64>,
-        t2: String,
-        foo: Vec<u32>,
-        foo2: Vec<u32>,
-        foo3: Option<Vec<u32>>,
+    struct TestDefaultsInner {
+        host: String,
+        port: u16,
+        level: String,
     }
 
     #[test]
-    fn test_from_env_flatten() {
+    fn test_from_env_with_defaults() {
+        let defaults = [
+            ("SERDE_ENV_DEFAULTS_HOST", "localhost"),
+            ("SERDE_ENV_DEFAULTS_PORT", "80"),
+            ("SERDE_ENV_DEFAULTS_LEVEL", "info"),
+        ];
         temp_env::with_vars(
-            vec![
-                ("port", Some("123")),
-                ("enable", Some("True")),
-                ("enable", Some("False")),
-                ("silent", Some("")),
-                ("t", Some("18446744073709551615")),
-                ("t2", Some("18446744073709551616")),
-                ("PUB_HOSTED_URL", Some("https://pub.dev")),
-                ("foo", Some("1,2,3")),
-                ("foo2", Some("1,2,")),
-                ("foo3", Some("1,")),
-                ("e", Some("X")),
-                ("e", Some("Z")),
-                ("e_a", Some("1")),
+            [
+                ("SERDE_ENV_DEFAULTS_PORT", Some("8080")),
+                ("SERDE_ENV_DEFAULTS_LEVEL", Some("")),
             ],
             || {
-                let n = Node::from_env();
+                let t: TestDefaults = from_env_with_defaults(defaults).expect("must success");
+                assert_eq!(
+                    t.serde_env_defaults,
+                    TestDefaultsInner {
+                        host: "localhost".to_string(),
+                        port: 8080,
+                        level: "info".to_string(),
+                    }
+                );
 
-                let t: TestFlatten =  TestFlatten::deserialize(Deserializer(n)).expect("must success");
-                dbg!(&t);
-                assert_eq!(t.inner.port, 123);
-                assert!(!t.inner.enable);
-                assert_eq!(t.pub_.hosted.url, t.pub_hosted_url);
-                assert_eq!(t.inner.foo, vec![1, 2, 3]);
+                let t: TestDefaults = EnvDeserializer::new()
+                    .empty_string_is_none(false)
+                    .from_env_with_defaults(defaults)
+                    .expect("must success");
+                assert_eq!(t.serde_env_defaults.level, "");
             },
-        )
+        );
+    }
+
+    #[test]
+    fn test_enum_case_insensitive() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Level {
+            Debug,
+            Info,
+            #[serde(rename = "x")]
+            LowerX,
+            X,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            level: Level,
+        }
+
+        let err = from_iter::<_, _, Test>([("LEVEL", "info")]).expect_err("must fail");
+        assert!(err.to_string().contains("no variant `info` found"), "{err}");
+
+        let de = EnvDeserializer::new().enum_case_insensitive(true);
+        let t: Test = de.from_iter([("LEVEL", "info")]).expect("must success");
+        assert_eq!(t.level, Level::Info);
+        let t: Test = de.from_iter([("LEVEL", "DEBUG")]).expect("must success");
+        assert_eq!(t.level, Level::Debug);
+
+        // Exact matches still win.
+        let t: Test = de.from_iter([("LEVEL", "x")]).expect("must success");
+        assert_eq!(t.level, Level::LowerX);
+        let t: Test = de.from_iter([("LEVEL", "X")]).expect("must success");
+        assert_eq!(t.level, Level::X);
+    }
+
+    #[test]
+    fn test_unknown_variant() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Mode {
+            X,
+            Y,
+            Z,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            mode: Mode,
+        }
+
+        let err = from_iter::<_, _, Test>([("MODE", "q")]).expect_err("must fail");
+        assert_eq!(
+            err.to_string(),
+            "invalid value for field `mode`: no variant `q` found, expected one of: X, Y, Z"
+        );
+    }
+
+    #[test]
+    fn test_enum_rename() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Status {
+            #[serde(rename = "active")]
+            Active,
+            #[serde(rename = "on-hold")]
+            OnHold,
+            Closed,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Kind {
+            FullTime,
+            PartTime,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            status: Status,
+            kind: Kind,
+        }
+
+        let t: Test =
+            from_iter([("STATUS", "active"), ("KIND", "part_time")]).expect("must success");
+        assert_eq!(t.status, Status::Active);
+        assert_eq!(t.kind, Kind::PartTime);
+        let t: Test =
+            from_iter([("STATUS", "on-hold"), ("KIND", "full_time")]).expect("must success");
+        assert_eq!(t.status, Status::OnHold);
+
+        // The original name no longer matches.
+        let err = from_iter::<_, _, Test>([("STATUS", "Active"), ("KIND", "full_time")])
+            .expect_err("must fail");
+        assert!(
+            err.to_string()
+                .contains("expected one of: active, on-hold, Closed"),
+            "{err}"
+        );
     }
 }
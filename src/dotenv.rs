@@ -0,0 +1,98 @@
+//! Parser of dotenv files like `.env`.
+
+use serde::de;
+
+use crate::error::Error;
+
+/// Parse lines like `KEY=VALUE` of a dotenv file.
+///
+/// Blank lines and comments starting with `#` are skipped. Keys and values
+/// are trimmed, and a leading `export ` is stripped so that files written for
+/// shells work unchanged. `name` is the source shown in errors, like
+/// `config.env`.
+pub(crate) fn parse(src: &str, name: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut vars = Vec::new();
+
+    for (idx, line) in src.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = strip_export(line);
+
+        match line.split_once('=') {
+            Some((k, v)) if !k.trim().is_empty() => {
+                vars.push((k.trim().to_string(), v.trim().to_string()))
+            }
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "invalid syntax at {name}:{}: expected KEY=VALUE",
+                    idx + 1
+                )))
+            }
+        }
+    }
+
+    Ok(vars)
+}
+
+/// Strip the leading `export ` of `export KEY=VALUE`.
+///
+/// A key literally named `export`, like `export=1` or `export = 1`, is kept.
+fn strip_export(line: &str) -> &str {
+    match line.strip_prefix("export") {
+        Some(rest) if rest.starts_with(char::is_whitespace) => {
+            let rest = rest.trim_start();
+            if rest.starts_with('=') {
+                line
+            } else {
+                rest
+            }
+        }
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let src = "# comment\n\nA=1\n  B = x y \nC=\nD=a=b\n";
+        let vars = parse(src, "test.env").expect("must success");
+        let expected = [("A", "1"), ("B", "x y"), ("C", ""), ("D", "a=b")];
+        assert_eq!(vars, expected.map(|(k, v)| (k.to_string(), v.to_string())));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let src = "# comment\nA=1\n\nB\n";
+        let err = parse(src, "config.env").expect_err("must fail");
+        assert_eq!(
+            err.to_string(),
+            "invalid syntax at config.env:4: expected KEY=VALUE"
+        );
+
+        let err = parse("=1", "config.env").expect_err("must fail");
+        assert!(err.to_string().contains("config.env:1"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_export() {
+        let src = "export FOO=1\nBAR=2\nexport\tBAZ = 3\nexport=4\nexport = 5\nexporter=6\n";
+        let vars = parse(src, "test.env").expect("must success");
+        let expected = [
+            ("FOO", "1"),
+            ("BAR", "2"),
+            ("BAZ", "3"),
+            ("export", "4"),
+            ("export", "5"),
+            ("exporter", "6"),
+        ];
+        assert_eq!(vars, expected.map(|(k, v)| (k.to_string(), v.to_string())));
+
+        let err = parse("export FOO\n", "test.env").expect_err("must fail");
+        assert!(err.to_string().contains("test.env:1"), "{err}");
+    }
+}
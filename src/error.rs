@@ -1,39 +1,311 @@
+use std::cell::RefCell;
 use std::fmt::{self, Debug, Display};
 
-use anyhow::anyhow;
 use serde::{de, ser};
 
+use crate::de::NestedKeys;
+
 /// Errors returned by serde-env.
 ///
-/// TODO: returns more meaning errors.
+/// Use [`kind`](Error::kind) to tell what went wrong.
+#[derive(Debug)]
+pub struct Error(Box<ErrorKind>);
+
+/// The kind of [`Error`].
 #[derive(Debug)]
-pub struct Error(anyhow::Error);
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A custom message, mostly from serde derives like an unknown variant.
+    Message(String),
+    /// A value can't be parsed into its field, like `abc` for `u32`.
+    Parse {
+        /// Name of the field, if known.
+        field: Option<String>,
+        /// The error from parsing.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A required field is not found in env.
+    MissingField(MissingField),
+    /// Several required fields are not found in env, returned by
+    /// [`try_from_env`](crate::try_from_env).
+    MissingFields(Vec<MissingField>),
+    /// A file can't be read, like the dotenv file of
+    /// [`from_file`](crate::from_file) or a `KEY_FILE`.
+    Io {
+        /// The env key pointing to the file, like `DB_PASSWORD_FILE`.
+        key: Option<String>,
+        /// Path of the file, or `<reader>` for [`from_reader`](crate::from_reader).
+        path: String,
+        /// The error from reading.
+        source: std::io::Error,
+    },
+}
 
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(anyhow!("{}", msg))
+        Error(Box::new(ErrorKind::Message(msg.to_string())))
     }
 }
 
+thread_local! {
+    /// Error passed through `de::Error::custom` by [`parse_str`].
+    static PENDING: RefCell<Option<Error>> = const { RefCell::new(None) };
+}
+
 impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(anyhow!("{}", msg))
+        PENDING
+            .with(|v| v.take())
+            .unwrap_or_else(|| Error(Box::new(ErrorKind::Message(msg.to_string()))))
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error(Box::new(ErrorKind::MissingField(MissingField {
+            field: field.to_string(),
+            key: None,
+        })))
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        match &*self.0 {
+            ErrorKind::Message(msg) => f.write_str(msg),
+            ErrorKind::Parse {
+                field: Some(field),
+                source,
+            } => write!(f, "invalid value for field `{field}`: {source}"),
+            ErrorKind::Parse {
+                field: None,
+                source,
+            } => Display::fmt(source, f),
+            ErrorKind::MissingField(v) => Display::fmt(v, f),
+            ErrorKind::MissingFields(fields) => {
+                f.write_str("missing fields: ")?;
+                for (idx, v) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "`{}` (env `{}`)", v.field, v.key())?;
+                }
+                Ok(())
+            }
+            ErrorKind::Io {
+                key: Some(key),
+                path,
+                source,
+            } => write!(f, "read `{key}` from {path}: {source}"),
+            ErrorKind::Io {
+                key: None,
+                path,
+                source,
+            } => write!(f, "read {path}: {source}"),
+        }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &*self.0 {
+            ErrorKind::Parse { source, .. } => Some(source.as_ref()),
+            ErrorKind::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 impl Error {
+    /// Create a parse error from the error of parsing a value.
     pub(crate) fn new<E>(err: E) -> Self
     where
-        E: std::error::Error,
+        E: std::error::Error + Send + Sync + 'static,
     {
-        Self(anyhow!("{}", err))
+        Self(Box::new(ErrorKind::Parse {
+            field: None,
+            source: Box::new(err),
+        }))
+    }
+
+    /// Create an error of reading the file at `path`.
+    pub(crate) fn io(key: Option<String>, path: String, source: std::io::Error) -> Self {
+        Self(Box::new(ErrorKind::Io { key, path, source }))
+    }
+
+    /// Create a missing field error for a field only known at runtime.
+    pub(crate) fn missing_field_with_key(field: &str, key: String) -> Self {
+        Self(Box::new(ErrorKind::MissingField(MissingField::new(
+            field, key,
+        ))))
+    }
+
+    pub(crate) fn missing_fields(fields: Vec<MissingField>) -> Self {
+        Self(Box::new(ErrorKind::MissingFields(fields)))
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0
+    }
+
+    /// Get the missing field of this error, if any.
+    pub(crate) fn missing_field(&self) -> Option<&MissingField> {
+        match &*self.0 {
+            ErrorKind::MissingField(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Set the env key of the missing field, if it's not set yet.
+    pub(crate) fn with_missing_key(mut self, key: impl FnOnce(&str) -> String) -> Self {
+        if let ErrorKind::MissingField(v) = &mut *self.0 {
+            if v.key.is_none() {
+                v.key = Some(key(&v.field));
+            }
+        }
+        self
+    }
+
+    /// Set the field of the parse error, if it's not set yet.
+    pub(crate) fn with_field(mut self, name: &str) -> Self {
+        if let ErrorKind::Parse {
+            field: field @ None,
+            source,
+        } = &mut *self.0
+        {
+            *field = Some(name.to_string());
+            if let Some(NestedKeys(keys)) = source.downcast_mut() {
+                for key in keys.iter_mut() {
+                    *key = format!("{name}_{key}");
+                }
+            }
+        }
+        self
+    }
+
+    /// All missing required fields, returned by
+    /// [`try_from_env`](crate::try_from_env).
+    ///
+    /// Returns an empty slice for other errors.
+    pub fn missing(&self) -> &[MissingField] {
+        match &*self.0 {
+            ErrorKind::MissingFields(v) => v,
+            _ => &[],
+        }
+    }
+}
+
+/// Deserialize a string and parse it, for `deserialize_with` helpers.
+///
+/// Deserializers of serde-env get the parse error as is, so that it's
+/// reported with the field name and keeps its source. Others only see the
+/// message.
+#[cfg(any(
+    feature = "base64",
+    feature = "chrono",
+    feature = "hex",
+    feature = "humantime",
+    feature = "json",
+    feature = "time"
+))]
+pub(crate) fn parse_str<'de, D, T>(
+    d: D,
+    parse: impl FnOnce(&str) -> Result<T, Error>,
+) -> Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let s = <String as de::Deserialize>::deserialize(d)?;
+    parse(&s).map_err(|err| {
+        let msg = err.to_string();
+        PENDING.with(|v| v.replace(Some(err)));
+        let err = de::Error::custom(msg);
+        // Left unused by other deserializers.
+        PENDING.with(|v| v.take());
+        err
+    })
+}
+
+/// A required field that is not found in env.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingField {
+    field: String,
+    key: Option<String>,
+}
+
+impl MissingField {
+    pub(crate) fn new(field: &str, key: String) -> Self {
+        Self {
+            field: field.to_string(),
+            key: Some(key),
+        }
+    }
+
+    /// Name of the field.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// The env key looked for, like `D_AA`.
+    pub fn key(&self) -> String {
+        self.key.as_deref().unwrap_or(&self.field).to_uppercase()
+    }
+
+    /// Full key of the field in node, which is lowercased.
+    pub(crate) fn path(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.field)
+    }
+}
+
+impl Display for MissingField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "missing field `{}`", self.field)
+    }
+}
+
+impl std::error::Error for MissingField {}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::from_iter;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Test {
+        name: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_kind() {
+        let err = from_iter::<_, _, Test>([("PORT", "80")]).expect_err("name is missing");
+        match err.kind() {
+            ErrorKind::MissingField(v) => assert_eq!(v.field(), "name"),
+            v => panic!("unexpected kind: {v:?}"),
+        }
+
+        let err = from_iter::<_, _, Test>([("NAME", "app"), ("PORT", "abc")])
+            .expect_err("port is invalid");
+        match err.kind() {
+            ErrorKind::Parse { field, .. } => assert_eq!(field.as_deref(), Some("port")),
+            v => panic!("unexpected kind: {v:?}"),
+        }
+        assert!(err.to_string().contains("`port`"), "{err}");
+    }
+
+    #[test]
+    fn test_source() {
+        use std::error::Error as _;
+        use std::num::ParseIntError;
+
+        let err = from_iter::<_, _, Test>([("NAME", "app"), ("PORT", "abc")])
+            .expect_err("port is invalid");
+        let source = err.source().expect("source must exist");
+        assert!(source.downcast_ref::<ParseIntError>().is_some());
+
+        let err = from_iter::<_, _, Test>([("PORT", "80")]).expect_err("name is missing");
+        assert!(err.source().is_none());
     }
 }
@@ -0,0 +1,139 @@
+//! Flags that are `true` if their key is set, like `DEBUG=1`.
+//!
+//! The value is never parsed, so `DEBUG=false` is `true` too. Unset keys
+//! never reach the deserializer, so flag fields need `#[serde(default)]` to
+//! be `false` when unset.
+//!
+//! Empty values like `DEBUG=` are seen as unset by default. Disable
+//! [`empty_string_is_none`](crate::EnvDeserializer::empty_string_is_none) to
+//! see them as set.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(default, deserialize_with = "serde_env::flag::deserialize")]
+//!     debug: bool,
+//!     #[serde(default, deserialize_with = "serde_env::flag::deserialize")]
+//!     verbose: bool,
+//! }
+//!
+//! let t: Test = from_iter([("DEBUG", "no")]).expect("deserialize from iter");
+//! assert!(t.debug);
+//! assert!(!t.verbose);
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserializer;
+
+/// Deserialize a flag, which is `true` whatever the value is.
+///
+/// Use it via
+/// `#[serde(default, deserialize_with = "serde_env::flag::deserialize")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(FlagVisitor)
+}
+
+/// Any value means set, except a map, which comes from nested keys like
+/// `DEBUG_LEVEL` while `DEBUG` itself is unset.
+struct FlagVisitor;
+
+impl<'de> Visitor<'de> for FlagVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+        Ok(true)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<bool, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(true)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<bool, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::{from_iter, EnvDeserializer};
+
+    #[derive(Deserialize, Debug)]
+    struct Test {
+        #[serde(default, deserialize_with = "super::deserialize")]
+        debug: bool,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let t: Test = from_iter([("DEBUG", "1")]).expect("must success");
+        assert!(t.debug);
+
+        let t: Test = from_iter([("DEBUG", "false")]).expect("must success");
+        assert!(t.debug);
+
+        let t: Test = from_iter([("DEBUG", "a,b")]).expect("must success");
+        assert!(t.debug);
+
+        let t: Test = from_iter([("DEBUG_LEVEL", "1")]).expect("must success");
+        assert!(!t.debug);
+
+        let t: Test = from_iter::<_, &str, _>([]).expect("must success");
+        assert!(!t.debug);
+    }
+
+    #[test]
+    fn test_deserialize_empty() {
+        let t: Test = from_iter([("DEBUG", "")]).expect("must success");
+        assert!(!t.debug);
+
+        let t: Test = EnvDeserializer::new()
+            .empty_string_is_none(false)
+            .from_iter([("DEBUG", "")])
+            .expect("must success");
+        assert!(t.debug);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::de::DeserializeOwned;
+
+use crate::de::from_env_with_prefix;
+use crate::error::Error;
+
+/// Config that is read from env with a fixed prefix.
+///
+/// Implement it once to attach the prefix to the type, instead of repeating
+/// `from_env_with_prefix("MYAPP")` at every call site.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::FromEnv;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct MyConfig {
+///     port: u16,
+/// }
+///
+/// impl FromEnv for MyConfig {
+///     const PREFIX: &'static str = "MYAPP";
+/// }
+///
+/// temp_env::with_var("MYAPP_PORT", Some("8080"), || {
+///     let config = MyConfig::from_env().expect("deserialize from env");
+///     assert_eq!(config.port, 8080);
+/// });
+/// ```
+pub trait FromEnv: DeserializeOwned {
+    /// The prefix without separator, like `MYAPP` for `MYAPP_PORT`.
+    const PREFIX: &'static str;
+
+    /// Deserialize from env with [`PREFIX`](FromEnv::PREFIX).
+    fn from_env() -> Result<Self, Error> {
+        from_env_with_prefix(Self::PREFIX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Test {
+        host: String,
+        db: Db,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Db {
+        port: u16,
+    }
+
+    impl FromEnv for Test {
+        const PREFIX: &'static str = "SERDE_ENV_FROM_ENV";
+    }
+
+    #[test]
+    fn test_from_env() {
+        temp_env::with_vars(
+            [
+                ("SERDE_ENV_FROM_ENV_HOST", Some("localhost")),
+                ("SERDE_ENV_FROM_ENV_DB_PORT", Some("5432")),
+                ("HOST", Some("ignored")),
+            ],
+            || {
+                let t = Test::from_env().expect("must success");
+                assert_eq!(
+                    t,
+                    Test {
+                        host: "localhost".to_string(),
+                        db: Db { port: 5432 },
+                    }
+                );
+            },
+        );
+
+        temp_env::with_var_unset("SERDE_ENV_FROM_ENV_HOST", || {
+            let err = Test::from_env().expect_err("host is missing");
+            assert!(err.to_string().contains("host"), "{err}");
+        });
+    }
+}
@@ -0,0 +1,75 @@
+//! Hex encoded bytes like `KEY=68656c6c6f`.
+//!
+//! Enabled by the `hex` feature. Both lower and upper case digits are
+//! accepted.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::hex::deserialize")]
+//!     key: Vec<u8>,
+//! }
+//!
+//! let t: Test = from_iter([("KEY", "68656c6c6f")]).expect("deserialize from iter");
+//! assert_eq!(t.key, b"hello");
+//! ```
+
+use serde::Deserializer;
+
+use crate::error::{parse_str, Error};
+
+/// Decode a hex string into bytes.
+pub fn parse(s: &str) -> Result<Vec<u8>, Error> {
+    ::hex::decode(s.trim()).map_err(Error::new)
+}
+
+/// Deserialize a hex string into bytes.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::hex::deserialize")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_str(d, parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::error::ErrorKind;
+    use crate::from_iter;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(deserialize_with = "super::deserialize")]
+        key: Vec<u8>,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let t: Test = from_iter([("KEY", "68656C6c6f")]).expect("must success");
+        assert_eq!(t.key, b"hello");
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let err = from_iter::<_, _, Test>([("KEY", "abc")]).expect_err("must fail");
+        assert!(err.to_string().contains("Odd number"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("key"));
+                assert!(source.downcast_ref::<::hex::FromHexError>().is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+
+        let err = super::parse("zz").expect_err("must fail");
+        assert!(err.to_string().contains("Invalid character"), "{err}");
+    }
+}
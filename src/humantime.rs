@@ -0,0 +1,83 @@
+//! Human readable durations like `TIMEOUT=1m 30s`.
+//!
+//! Enabled by the `humantime` feature. See [`humantime::parse_duration`]
+//! for the accepted format.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::humantime::deserialize")]
+//!     timeout: Duration,
+//! }
+//!
+//! let t: Test = from_iter([("TIMEOUT", "1m 30s")]).expect("deserialize from iter");
+//! assert_eq!(t.timeout, Duration::from_secs(90));
+//! ```
+
+use std::time::Duration;
+
+use serde::Deserializer;
+
+use crate::error::{parse_str, Error};
+
+/// Parse a human readable duration like `1h 30m`.
+pub fn parse(s: &str) -> Result<Duration, Error> {
+    ::humantime::parse_duration(s.trim()).map_err(Error::new)
+}
+
+/// Deserialize a human readable duration.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::humantime::deserialize")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    parse_str(d, parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::Deserialize;
+
+    use crate::error::ErrorKind;
+    use crate::from_iter;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(deserialize_with = "super::deserialize")]
+        timeout: Duration,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let t: Test = from_iter([("TIMEOUT", "2h 5ms")]).expect("must success");
+        assert_eq!(
+            t.timeout,
+            Duration::from_secs(7200) + Duration::from_millis(5)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let err = from_iter::<_, _, Test>([("TIMEOUT", "10 parsecs")]).expect_err("must fail");
+        assert!(err.to_string().contains("unknown time unit"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("timeout"));
+                assert!(source
+                    .downcast_ref::<::humantime::DurationError>()
+                    .is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+    }
+}
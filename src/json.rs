@@ -0,0 +1,155 @@
+//! Embedded JSON values like `FEATURES={"a":true,"b":3}`.
+//!
+//! Enabled by the `json` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::json::deserialize")]
+//!     features: HashMap<String, serde_json::Value>,
+//! }
+//!
+//! let t: Test =
+//!     from_iter([("FEATURES", r#"{"a":true,"b":3}"#)]).expect("deserialize from iter");
+//! assert_eq!(t.features["b"], 3);
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Deserializer;
+
+use crate::error::{parse_str, Error};
+
+/// Parse a JSON string into `T`.
+pub fn parse<T>(s: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_str(s).map_err(Error::new)
+}
+
+/// Deserialize a JSON string into `T`.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::json::deserialize")]`.
+pub fn deserialize<'de, D, T>(d: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    parse_str(d, parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::error::ErrorKind;
+    use crate::{from_iter, EnvDeserializer};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Features {
+        a: bool,
+        b: u32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(deserialize_with = "super::deserialize")]
+        features: Features,
+        #[serde(deserialize_with = "super::deserialize")]
+        hosts: Vec<String>,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let vars = [
+            ("FEATURES", r#"{"a":true,"b":3}"#),
+            ("HOSTS", r#"["a.example", "b.example"]"#),
+        ];
+
+        let t: Test = from_iter(vars).expect("must success");
+        assert_eq!(
+            t,
+            Test {
+                features: Features { a: true, b: 3 },
+                hosts: vec!["a.example".to_string(), "b.example".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_invalid() {
+        let vars = [("FEATURES", r#"{"a":true,"#), ("HOSTS", "[]")];
+
+        let err = from_iter::<_, _, Test>(vars).expect_err("must fail");
+        assert!(err.to_string().contains("EOF"), "{err}");
+        match err.kind() {
+            ErrorKind::Parse { field, source } => {
+                assert_eq!(field.as_deref(), Some("features"));
+                assert!(source.downcast_ref::<serde_json::Error>().is_some());
+            }
+            v => panic!("unexpected kind: {v:?}"),
+        }
+
+        let err = super::parse::<HashMap<String, u32>>(r#"{"a":"x"}"#).expect_err("must fail");
+        assert!(err.to_string().contains("invalid type"), "{err}");
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: Option<u16>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct TestSeq {
+        servers: Vec<Server>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_json_seq() {
+        let vars = [
+            ("SERVERS", r#"[{"host":"a"}, {"host":"b","port":8080}]"#),
+            ("TAGS", "x,y"),
+        ];
+
+        let t: TestSeq = EnvDeserializer::new()
+            .json_seq(true)
+            .from_iter(vars)
+            .expect("must success");
+        assert_eq!(
+            t,
+            TestSeq {
+                servers: vec![
+                    Server {
+                        host: "a".to_string(),
+                        port: None
+                    },
+                    Server {
+                        host: "b".to_string(),
+                        port: Some(8080)
+                    },
+                ],
+                tags: vec!["x".to_string(), "y".to_string()],
+            }
+        );
+
+        // Disabled by default.
+        from_iter::<_, _, TestSeq>(vars).expect_err("must fail");
+
+        let err = EnvDeserializer::new()
+            .json_seq(true)
+            .from_iter::<_, _, TestSeq>([("SERVERS", r#"[{"host":"a"},"#), ("TAGS", "x")])
+            .expect_err("must fail");
+        assert!(err.to_string().contains("`servers`"), "{err}");
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt::{self, Debug, Formatter};
+
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+
+use crate::builder::EnvDeserializer;
+use crate::error::Error;
+
+/// Config deserialized from env once on first access, which is useful for
+/// global config in statics.
+///
+/// Enabled by the `once_cell` feature.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::LazyEnv;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     #[serde(default)]
+///     log_level: String,
+/// }
+///
+/// static CONFIG: LazyEnv<Config> = LazyEnv::new();
+///
+/// let config: &'static Config = CONFIG.get();
+/// println!("{}", config.log_level);
+/// ```
+pub struct LazyEnv<T> {
+    prefix: Option<&'static str>,
+    cell: OnceCell<Result<T, Error>>,
+}
+
+impl<T> LazyEnv<T> {
+    /// Create a lazy config read from env.
+    pub const fn new() -> Self {
+        Self {
+            prefix: None,
+            cell: OnceCell::new(),
+        }
+    }
+
+    /// Create a lazy config read from env with a prefix.
+    pub const fn with_prefix(prefix: &'static str) -> Self {
+        Self {
+            prefix: Some(prefix),
+            cell: OnceCell::new(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> LazyEnv<T> {
+    /// Get the config, deserializing it on first access.
+    ///
+    /// The result is cached, so failures are not retried.
+    pub fn try_get(&self) -> Result<&T, &Error> {
+        self.cell
+            .get_or_init(|| match self.prefix {
+                None => EnvDeserializer::new().from_env(),
+                Some(prefix) => EnvDeserializer::new().from_prefix(prefix),
+            })
+            .as_ref()
+    }
+
+    /// Get the config, deserializing it on first access.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the config can't be deserialized from env.
+    pub fn get(&self) -> &T {
+        match self.try_get() {
+            Ok(v) => v,
+            Err(err) => panic!("deserialize from env: {err}"),
+        }
+    }
+}
+
+impl<T> Default for LazyEnv<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for LazyEnv<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyEnv")
+            .field("prefix", &self.prefix)
+            .field("value", &self.cell.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde::{Deserialize, Deserializer};
+
+    use super::*;
+
+    static LOADED: AtomicUsize = AtomicUsize::new(0);
+
+    fn counted<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+        LOADED.fetch_add(1, Ordering::SeqCst);
+        String::deserialize(d)
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Config {
+        #[serde(deserialize_with = "counted")]
+        name: String,
+    }
+
+    static CONFIG: LazyEnv<Config> = LazyEnv::with_prefix("TEST_LAZY");
+
+    static MISSING: LazyEnv<Config> = LazyEnv::with_prefix("TEST_LAZY_MISSING");
+
+    #[test]
+    fn test_lazy_env() {
+        temp_env::with_var("TEST_LAZY_NAME", Some("lazy"), || {
+            assert_eq!(CONFIG.get().name, "lazy");
+            assert_eq!(CONFIG.get().name, "lazy");
+        });
+        assert_eq!(CONFIG.get().name, "lazy");
+        assert_eq!(LOADED.load(Ordering::SeqCst), 1);
+
+        assert!(MISSING.try_get().is_err());
+    }
+}
@@ -31,10 +31,93 @@
 //! assert!(!t.cargo.home.is_empty());
 //! println!("{:?}", t)
 //! ```
+//!
+//! `#[cfg(windows)]` above is needed because `HOME` is usually not set on
+//! Windows, not because of casing: env keys are lowercased before matching
+//! on every platform, so `USERPROFILE`, `UserProfile` and `userprofile` all
+//! match the field `userprofile`.
+//!
+//! # Untagged enums
+//!
+//! `#[serde(untagged)]` enums are supported by trying variants in order, so
+//! ambiguous values resolve to the first matching variant. For `u64 | String`,
+//! `42` is the integer and `hello` is the string, while for `String | u64`
+//! both are strings.
+//!
+//! # Secrets
+//!
+//! With the `secrecy` feature enabled, fields can be typed as
+//! `secrecy::SecretString` so that secret values are zeroized on drop.
+//! The value is moved into the secret without any extra copy made by
+//! serde-env during deserialization, but the following copies are still
+//! out of our control:
+//!
+//! - The process environment itself, which lives until the process exits.
+//! - The snapshot of env built before deserializing, which is dropped
+//!   (not zeroized) after deserializing.
+//!
+//! # Features
+//!
+//! All features are off by default, so the core only depends on `serde`.
+//!
+//! | Feature     | Enables                                                           |
+//! |-------------|-------------------------------------------------------------------|
+//! | `base64`    | `serde_env::base64` and `ByteEncoding::Base64` for base64 bytes   |
+//! | `chrono`    | `serde_env::datetime` for RFC 3339 `chrono::DateTime` values      |
+//! | `hex`       | `serde_env::hex` and `ByteEncoding::Hex` for hex bytes            |
+//! | `humantime` | `serde_env::humantime` for durations like `1m 30s`                |
+//! | `json`      | `serde_env::json` and `EnvDeserializer::json_seq` for JSON values |
+//! | `once_cell` | `serde_env::LazyEnv`, see [Global config](#global-config)         |
+//! | `secrecy`   | `secrecy::SecretString` fields, see [Secrets](#secrets)           |
+//! | `time`      | `serde_env::datetime` for RFC 3339 `time::OffsetDateTime` values  |
+//!
+//! Each helper module exposes a `deserialize` function to be used via
+//! `#[serde(deserialize_with = "...")]`.
+//!
+//! # Global config
+//!
+//! With the `once_cell` feature enabled, `serde_env::LazyEnv` can be used
+//! in statics to deserialize config from env once on first access.
 
+#[cfg(feature = "base64")]
+pub mod base64;
+mod builder;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod datetime;
 mod de;
+mod dotenv;
 pub mod error;
+pub mod flag;
+mod from_env;
+#[cfg(feature = "hex")]
+pub mod hex;
+#[cfg(feature = "humantime")]
+pub mod humantime;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "once_cell")]
+mod lazy;
+pub mod offset;
+pub mod paths;
+mod prefixed;
+pub mod prelude;
+mod report;
 mod value;
 
-pub use de::{from_env, from_env_with_prefix, from_iter, from_iter_with_prefix};
-pub use error::Error;
+pub use builder::{ByteEncoding, EnvDeserializer, SeqInference};
+pub use de::{
+    deserialize_field, from_env, from_env_filtered, from_env_or_default, from_env_tracked,
+    from_env_with_defaults, from_env_with_prefix, from_env_with_prefix_or_default,
+    from_env_with_prefixes, from_env_with_report, from_file, from_iter, from_iter_os,
+    from_iter_with_key_map, from_iter_with_prefix, from_map, from_map_with_prefix, from_node,
+    from_reader, try_from_env, validate_env,
+};
+pub use error::{Error, ErrorKind, MissingField};
+pub use from_env::FromEnv;
+#[cfg(feature = "once_cell")]
+pub use lazy::LazyEnv;
+pub use prefixed::{Prefix, Prefixed};
+pub use report::{ConfigReport, ReportEntry, Source};
+#[cfg(feature = "secrecy")]
+pub use secrecy;
+pub use value::Node;
@@ -0,0 +1,116 @@
+//! Relative time offsets like `+30s`.
+//!
+//! [`Instant`](std::time::Instant) has no absolute representation, so it
+//! can't be deserialized from env. Instead, deserialize an offset into a
+//! [`Duration`] and apply it to `Instant::now()` by yourself.
+//!
+//! An offset is an optional `+` followed by one or more `<number><unit>`
+//! segments, like `+30s` or `+1h30m`. Supported units are `ns`, `us`, `ms`,
+//! `s`, `m`, `h` and `d`.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//!
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(with = "serde_env::offset")]
+//!     deadline: Duration,
+//! }
+//!
+//! let t: Test = from_iter([("DEADLINE", "+30s")]).expect("deserialize from iter");
+//! assert_eq!(t.deadline, Duration::from_secs(30));
+//!
+//! let _deadline = Instant::now() + t.deadline;
+//! ```
+
+use std::time::Duration;
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::error::Error;
+
+/// Parse a relative offset like `+30s` into [`Duration`].
+pub fn parse(s: &str) -> Result<Duration, Error> {
+    let offset = s.trim();
+    if offset.starts_with('-') {
+        return Err(de::Error::custom(format!(
+            "negative offset `{s}` is not supported"
+        )));
+    }
+    let mut rest = offset.strip_prefix('+').unwrap_or(offset);
+    if rest.is_empty() {
+        return Err(de::Error::custom(format!("invalid offset `{s}`")));
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| de::Error::custom(format!("missing unit in offset `{s}`")))?;
+        let (number, remain) = rest.split_at(digits);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| de::Error::custom(format!("invalid number in offset `{s}`")))?;
+
+        let unit = remain
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(remain.len());
+        let (unit, remain) = remain.split_at(unit);
+        let duration = match unit {
+            "ns" => Duration::from_nanos(number),
+            "us" => Duration::from_micros(number),
+            "ms" => Duration::from_millis(number),
+            "s" => Duration::from_secs(number),
+            "m" => Duration::from_secs(number.saturating_mul(60)),
+            "h" => Duration::from_secs(number.saturating_mul(60 * 60)),
+            "d" => Duration::from_secs(number.saturating_mul(24 * 60 * 60)),
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "unknown unit `{unit}` in offset `{s}`"
+                )))
+            }
+        };
+
+        total = total.saturating_add(duration);
+        rest = remain;
+    }
+
+    Ok(total)
+}
+
+/// Deserialize a relative offset like `+30s` into [`Duration`].
+///
+/// Use it via `#[serde(with = "serde_env::offset")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(d)?;
+    parse(&s).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("+30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("+1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("+250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse("+2d").unwrap(), Duration::from_secs(172800));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in ["", "+", "-30s", "+30", "+30x", "+s"] {
+            assert!(parse(s).is_err(), "{s} must be invalid");
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Path lists like `PATH`, split by the platform path separator.
+//!
+//! The separator is `:` on Unix and `;` on Windows, as
+//! [`std::env::split_paths`] does, instead of the list delimiter. So Windows
+//! paths like `C:\bin` are never split at the drive letter.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::env;
+//! use std::path::PathBuf;
+//!
+//! use serde::Deserialize;
+//! use serde_env::from_iter;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     #[serde(deserialize_with = "serde_env::paths::deserialize")]
+//!     search_paths: Vec<PathBuf>,
+//! }
+//!
+//! let value = env::join_paths(["/usr/bin", "/opt/bin"]).expect("join paths");
+//! let value = value.to_str().expect("valid unicode");
+//! let t: Test = from_iter([("SEARCH_PATHS", value)]).expect("deserialize from iter");
+//! assert_eq!(t.search_paths, [PathBuf::from("/usr/bin"), PathBuf::from("/opt/bin")]);
+//! ```
+
+use std::env;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Deserializer};
+
+/// Split a path list into paths.
+pub fn parse(s: &str) -> Vec<PathBuf> {
+    env::split_paths(s).collect()
+}
+
+/// Deserialize a path list into paths.
+///
+/// Use it via `#[serde(deserialize_with = "serde_env::paths::deserialize")]`.
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(d)?;
+    Ok(parse(&s))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::from_iter;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(deserialize_with = "super::deserialize")]
+        search_paths: Vec<PathBuf>,
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deserialize_unix() {
+        let t: Test = from_iter([("SEARCH_PATHS", "/usr/bin:/opt/a,b/bin")]).expect("must success");
+        assert_eq!(
+            t.search_paths,
+            [PathBuf::from("/usr/bin"), PathBuf::from("/opt/a,b/bin")]
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_deserialize_windows() {
+        let t: Test = from_iter([("SEARCH_PATHS", r"C:\bin;D:\a,b\bin")]).expect("must success");
+        assert_eq!(
+            t.search_paths,
+            [PathBuf::from(r"C:\bin"), PathBuf::from(r"D:\a,b\bin")]
+        );
+    }
+}
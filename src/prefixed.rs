@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::value::SeqAccessDeserializer;
+use serde::de::{
+    self, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::{Deserialize, Deserializer};
+
+/// Prefix of keys captured by [`Prefixed`].
+pub trait Prefix {
+    /// The prefix, like `LABEL_`. Keys are matched case-insensitively.
+    const PREFIX: &'static str;
+}
+
+/// A map that captures only keys starting with `P::PREFIX`.
+///
+/// A `#[serde(flatten)]` map captures all unknown keys, so several flattened
+/// maps in one struct would capture the same keys. Wrap them in `Prefixed`
+/// to route keys by their prefix instead. The prefix is stripped from keys
+/// in the map, and other keys are ignored.
+///
+/// Nested keys like `LABEL_A_B` are kept as flat keys like `a_b`.
+///
+/// Values are parsed into `V` like values of a plain `#[serde(flatten)]`
+/// map, so `1.5` works for `f64`. Prefer it over such a map for nested keys
+/// like `METRICS_CPU`, which serde buffers as a map under `metrics` in case
+/// a flattened struct has a `metrics` field.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_env::{from_iter, Prefix, Prefixed};
+///
+/// struct Label;
+/// impl Prefix for Label {
+///     const PREFIX: &'static str = "LABEL_";
+/// }
+///
+/// struct Annotation;
+/// impl Prefix for Annotation {
+///     const PREFIX: &'static str = "ANNOTATION_";
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Test {
+///     #[serde(flatten)]
+///     labels: Prefixed<Label>,
+///     #[serde(flatten)]
+///     annotations: Prefixed<Annotation>,
+/// }
+///
+/// let t: Test = from_iter([("LABEL_A", "1"), ("ANNOTATION_B", "2")])
+///     .expect("deserialize from iter");
+///
+/// assert_eq!(t.labels.get("a").map(String::as_str), Some("1"));
+/// assert_eq!(t.annotations.get("b").map(String::as_str), Some("2"));
+/// assert_eq!(t.labels.len(), 1);
+/// ```
+pub struct Prefixed<P, V = String> {
+    map: HashMap<String, V>,
+    _prefix: PhantomData<P>,
+}
+
+impl<P, V> Prefixed<P, V> {
+    /// Into the inner map to get ownership.
+    pub fn into_inner(self) -> HashMap<String, V> {
+        self.map
+    }
+}
+
+impl<P, V> Default for Prefixed<P, V> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            _prefix: PhantomData,
+        }
+    }
+}
+
+impl<P, V: Debug> Debug for Prefixed<P, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<P, V> Deref for Prefixed<P, V> {
+    type Target = HashMap<String, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<P, V> DerefMut for Prefixed<P, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+impl<'de, P, V> Deserialize<'de> for Prefixed<P, V>
+where
+    P: Prefix,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_map(PrefixedVisitor(PhantomData))
+    }
+}
+
+struct PrefixedVisitor<P, V>(PhantomData<(P, V)>);
+
+impl<'de, P, V> Visitor<'de> for PrefixedVisitor<P, V>
+where
+    P: Prefix,
+    V: Deserialize<'de>,
+{
+    type Value = Prefixed<P, V>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a map with keys prefixed by `{}`", P::PREFIX)
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let prefix = P::PREFIX.to_lowercase();
+        let mut map = Prefixed::default();
+
+        while let Some(key) = access.next_key::<String>()? {
+            match key.to_lowercase().strip_prefix(&prefix) {
+                Some(k) if !k.is_empty() => {
+                    let k = k.to_string();
+                    if let Some(v) = access.next_value_seed(LeafSeed(PhantomData))? {
+                        map.insert(k, v);
+                    }
+                }
+                _ => {
+                    access.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Deserialize a typed value into `V`, with its string form as fallback,
+/// since env values are strings in the first place.
+fn forward<'de, T, V, E>(v: T, s: String) -> Result<Option<V>, E>
+where
+    T: IntoDeserializer<'de, E>,
+    V: Deserialize<'de>,
+    E: de::Error,
+{
+    V::deserialize(v.into_deserializer())
+        .or_else(|_| V::deserialize(s.into_deserializer()))
+        .map(Some)
+}
+
+/// Deserialize a leaf value into `V`, or `None` for the intermediate node of
+/// nested keys, which is visited as map.
+struct LeafSeed<V>(PhantomData<V>);
+
+impl<'de, V> DeserializeSeed<'de> for LeafSeed<V>
+where
+    V: Deserialize<'de>,
+{
+    type Value = Option<V>;
+
+    fn deserialize<D>(self, d: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(self)
+    }
+}
+
+impl<'de, V> Visitor<'de> for LeafSeed<V>
+where
+    V: Deserialize<'de>,
+{
+    type Value = Option<V>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a leaf value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        forward(v, v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        forward(v, v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        forward(v, v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        forward(v, v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        V::deserialize(v.into_deserializer()).map(Some)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        V::deserialize(v.into_deserializer()).map(Some)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        V::deserialize(().into_deserializer()).map(Some)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        V::deserialize(().into_deserializer()).map(Some)
+    }
+
+    fn visit_some<D>(self, d: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        V::deserialize(d).map(Some)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        V::deserialize(SeqAccessDeserializer::new(seq)).map(Some)
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while access.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_iter;
+
+    struct Label;
+    impl Prefix for Label {
+        const PREFIX: &'static str = "LABEL_";
+    }
+
+    struct Annotation;
+    impl Prefix for Annotation {
+        const PREFIX: &'static str = "ANNOTATION_";
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Test {
+        name: String,
+        #[serde(flatten)]
+        labels: Prefixed<Label>,
+        #[serde(flatten)]
+        annotations: Prefixed<Annotation, u32>,
+    }
+
+    #[test]
+    fn test_prefixed() {
+        let vars = [
+            ("NAME", "test"),
+            ("LABEL_A", "x"),
+            ("LABEL_B_C", "y"),
+            ("ANNOTATION_B", "1"),
+            ("OTHER", "z"),
+        ];
+
+        let t: Test = from_iter(vars).expect("must success");
+        assert_eq!(t.name, "test");
+        assert_eq!(
+            t.labels.into_inner(),
+            HashMap::from([
+                ("a".to_string(), "x".to_string()),
+                ("b_c".to_string(), "y".to_string()),
+            ])
+        );
+        assert_eq!(
+            t.annotations.into_inner(),
+            HashMap::from([("b".to_string(), 1)])
+        );
+    }
+
+    struct Metric;
+    impl Prefix for Metric {
+        const PREFIX: &'static str = "METRIC_";
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct TestMetrics {
+        #[serde(flatten)]
+        metrics: Prefixed<Metric, f64>,
+    }
+
+    #[test]
+    fn test_prefixed_typed() {
+        let vars = [("METRIC_CPU", "3"), ("METRIC_MEM", "1.5"), ("OTHER", "x")];
+        let t: TestMetrics = from_iter(vars).expect("must success");
+        assert_eq!(
+            t.metrics.into_inner(),
+            HashMap::from([("cpu".to_string(), 3.0), ("mem".to_string(), 1.5)])
+        );
+
+        let err = from_iter::<_, _, TestMetrics>([("METRIC_CPU", "high")]).expect_err("must fail");
+        assert!(err.to_string().contains("expected f64"), "{err}");
+    }
+}
@@ -0,0 +1,37 @@
+//! Common items for a glob import.
+//!
+//! It re-exports the entry functions, the builder and the helper modules for
+//! `#[serde(deserialize_with = "...")]`, including helpers enabled by
+//! features.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_env::prelude::*;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Test {
+//!     name: String,
+//!     #[serde(default, deserialize_with = "flag::deserialize")]
+//!     debug: bool,
+//! }
+//!
+//! let t: Test = from_iter([("NAME", "app"), ("DEBUG", "1")]).expect("deserialize from iter");
+//! assert_eq!(t.name, "app");
+//! assert!(t.debug);
+//! ```
+
+#[cfg(feature = "base64")]
+pub use crate::base64;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use crate::datetime;
+#[cfg(feature = "hex")]
+pub use crate::hex;
+#[cfg(feature = "humantime")]
+pub use crate::humantime;
+#[cfg(feature = "json")]
+pub use crate::json;
+pub use crate::{
+    flag, from_env, from_env_with_prefix, from_iter, offset, paths, EnvDeserializer, Error, FromEnv,
+};
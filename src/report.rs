@@ -0,0 +1,63 @@
+use std::fmt::{self, Display};
+
+/// Where the value of a field comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The field is read from env.
+    Env,
+    /// The field is not found in env, so its default is used.
+    Default,
+}
+
+/// A field recorded in [`ConfigReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportEntry {
+    /// The env key of this field, like `D_AA`.
+    pub key: String,
+    /// Where the value of this field comes from.
+    pub source: Source,
+    /// The value read from env.
+    ///
+    /// It's `None` if the field uses its default, or the field is redacted.
+    pub value: Option<String>,
+    /// Whether the value is hidden by the redaction hook.
+    pub redacted: bool,
+}
+
+/// A report of the loaded configuration, which is useful for startup logging.
+///
+/// Returned by [`from_env_with_report`](crate::from_env_with_report).
+///
+/// Every struct field visited during deserializing is recorded with its env
+/// key, the source of its value and the value itself. Nested structs are
+/// recorded by their fields instead of themselves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigReport {
+    pub(crate) entries: Vec<ReportEntry>,
+}
+
+impl ConfigReport {
+    /// Entries in this report, ordered by visiting order.
+    pub fn entries(&self) -> &[ReportEntry] {
+        &self.entries
+    }
+
+    /// Get the entry of an env key.
+    pub fn get(&self, key: &str) -> Option<&ReportEntry> {
+        self.entries.iter().find(|v| v.key == key)
+    }
+}
+
+impl Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            match (entry.source, &entry.value) {
+                (Source::Default, _) => writeln!(f, "{} (default)", entry.key)?,
+                (Source::Env, _) if entry.redacted => writeln!(f, "{}=*** (env)", entry.key)?,
+                (Source::Env, Some(v)) => writeln!(f, "{}={} (env)", entry.key, v)?,
+                (Source::Env, None) => writeln!(f, "{} (env)", entry.key)?,
+            }
+        }
+        Ok(())
+    }
+}
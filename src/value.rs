@@ -1,7 +1,11 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
 use std::fmt::{Debug, Formatter};
 use std::{env, fmt};
 
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
 /// Node represents a tree of env values.
 ///
 /// Every env will be separated by `_` in key to construct this tree.
@@ -9,149 +13,301 @@ use std::{env, fmt};
 /// - `ABC=123` => `Node("123", {})`
 /// - `ABC_DEF=123` => `Node("", { "DEF": Node("123", {}) })`
 /// - `ABC=123,ABC_DEF=456` => `Node("123", { "DEF": Node("456", {}) })`
-#[derive(PartialEq, Clone)]
-pub(crate) struct Node(String, BTreeMap<String, Node>);
+///
+/// Every node also records whether its key was actually supplied, so that
+/// `ABC=` (present but empty) can be told apart from an unset `ABC`.
+///
+/// Build a node via [`Node::from_env`] or [`FromIterator`], and deserialize
+/// it via [`from_node`](crate::from_node), which can borrow from the node.
+#[derive(PartialEq, Clone, Default)]
+pub struct Node {
+    value: String,
+    /// Raw bytes of the value, only kept when the value is not valid UTF-8.
+    raw: Option<Vec<u8>>,
+    /// Whether this key is supplied, even if its value is empty.
+    present: bool,
+    children: BTreeMap<String, Node>,
+}
 
 impl Debug for Node {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if self.0.is_empty() {
+        if self.value.is_empty() {
             // Value is empty, print inner map instead.
-            f.debug_map().entries(&self.1).finish()
-        } else if self.1.is_empty() {
+            f.debug_map().entries(&self.children).finish()
+        } else if self.children.is_empty() {
             // inner map is empty too, print value instead.
-            f.write_str(&self.0)
+            f.write_str(&self.value)
         } else {
             // Print as list.
             // The first entry is value, and the second is inner map.
-            f.debug_list().entry(&self.0).entry(&self.1).finish()
+            f.debug_list()
+                .entry(&self.value)
+                .entry(&self.children)
+                .finish()
         }
     }
 }
 
 impl Node {
-    /// Create a new node without children
+    /// Create a new present node without children
     pub(crate) fn new(v: impl Into<String>) -> Self {
-        Node(v.into(), BTreeMap::new())
+        Node {
+            value: v.into(),
+            present: true,
+            ..Default::default()
+        }
     }
 
     /// Get value from node.
-    pub(crate) fn value(&self) -> &str {
-        &self.0
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the raw bytes of value.
+    ///
+    /// Differs from `value().as_bytes()` only if the node is built from
+    /// an `OsStr` that is not valid UTF-8.
+    pub(crate) fn value_bytes(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or(self.value.as_bytes())
     }
 
     /// Into value to get ownership.
     pub(crate) fn into_value(self) -> String {
-        self.0
+        self.value
     }
-    
-    pub(crate) fn childrens(&self) -> Vec<String> {
-        self.1.keys().cloned().collect()
+
+    /// Into raw bytes of value to get ownership.
+    pub(crate) fn into_value_bytes(self) -> Vec<u8> {
+        self.raw.unwrap_or_else(|| self.value.into_bytes())
     }
 
+    /// Check if this node has neither a value nor children.
+    ///
+    /// A node with children is never empty even if its own value is, like
+    /// `a` of `A_B=1`.
     pub(crate) fn is_empty(&self) -> bool {
-        self.0.is_empty() && self.1.is_empty()
+        self.value.is_empty() && self.children.is_empty()
+    }
+
+    /// Check if neither this key nor any nested key has a non-empty value,
+    /// like `INNER_A=` and `INNER_B_C=` for `inner`.
+    pub(crate) fn is_empty_tree(&self) -> bool {
+        self.value.is_empty() && self.children.values().all(Node::is_empty_tree)
+    }
+
+    /// Check if this key is not supplied at all and has no children.
+    pub(crate) fn is_absent(&self) -> bool {
+        !self.present && self.children.is_empty()
+    }
+
+    /// Check if this key is supplied, even if its value is empty.
+    pub(crate) fn is_present(&self) -> bool {
+        self.present
+    }
+
+    /// Check if this key is supplied but its value is empty, like `ABC=`.
+    pub(crate) fn is_present_empty(&self) -> bool {
+        self.present && self.value.is_empty()
     }
 
     pub(crate) fn has_children(&self) -> bool {
-        !self.1.is_empty()
+        !self.children.is_empty()
     }
 
-    pub(crate) fn flatten(&self, prefix: &str) -> HashSet<String> {
-        let mut m = HashSet::new();
+    /// Check if all children are indexed by number, like `a_0`, `a_1`.
+    pub(crate) fn has_indexed_children(&self) -> bool {
+        self.has_children() && self.children.keys().all(|k| k.parse::<usize>().is_ok())
+    }
 
-        for (key, value) in self.1.iter() {
-            let prefix_key = if prefix.is_empty() {
-                key.to_string()
-            } else {
-                format!("{prefix}_{key}")
-            };
+    /// Into children sorted by their numeric index.
+    ///
+    /// Children whose key is not a number are dropped.
+    pub(crate) fn into_indexed_children(self) -> Vec<(usize, Node)> {
+        let mut children: Vec<_> = self
+            .children
+            .into_iter()
+            .filter_map(|(k, v)| k.parse().ok().map(|idx| (idx, v)))
+            .collect();
+        children.sort_by_key(|(idx, _)| *idx);
+        children
+    }
+
+    /// Keys of direct children.
+    pub(crate) fn childrens(&self) -> Vec<String> {
+        self.children.keys().cloned().collect()
+    }
+
+    /// Keys of direct children, sorted like [`flatten`](Self::flatten).
+    pub(crate) fn sorted_keys(&self) -> Vec<String> {
+        self.sorted_children()
+            .into_iter()
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    /// Direct children sorted by key, with numeric keys first in numeric
+    /// order, so `2` comes before `10`.
+    fn sorted_children(&self) -> Vec<(&String, &Node)> {
+        // Keys are sorted as strings already, and the sort is stable.
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by_cached_key(|(k, _)| {
+            let idx = k.parse::<usize>().ok();
+            (idx.is_none(), idx)
+        });
+        children
+    }
 
-            if !value.0.is_empty() {
-                m.insert(prefix_key.clone());
+    /// Indexed children sorted by their numeric index.
+    ///
+    /// Children whose key is not a number are skipped.
+    pub(crate) fn indexed_children(&self) -> Vec<(usize, &Node)> {
+        let mut children: Vec<_> = self
+            .children
+            .iter()
+            .filter_map(|(k, v)| k.parse().ok().map(|idx| (idx, v)))
+            .collect();
+        children.sort_by_key(|(idx, _)| *idx);
+        children
+    }
+
+    /// Full keys of all present or intermediate nodes, sorted.
+    ///
+    /// Numeric keys are sorted by number, so `a_2` comes before `a_10`.
+    pub(crate) fn flatten(&self) -> Vec<String> {
+        let mut keys = Vec::with_capacity(self.count());
+        self.flatten_into(&mut String::new(), &mut keys);
+        keys
+    }
+
+    /// Write full keys into `keys`, with `buf` as the reused key prefix.
+    ///
+    /// `buf` is restored after return, so no key is formatted from scratch.
+    pub(crate) fn flatten_into(&self, buf: &mut String, keys: &mut Vec<String>) {
+        let len = buf.len();
+
+        for (key, value) in self.sorted_children() {
+            if len > 0 {
+                buf.push('_');
             }
-            if !value.1.is_empty() {
-                m.insert(prefix_key.clone());
-                m.extend(value.flatten(&prefix_key))
+            buf.push_str(key);
+
+            if value.present || value.has_children() {
+                keys.push(buf.clone());
             }
+            value.flatten_into(buf, keys);
+
+            buf.truncate(len);
         }
+    }
 
-        m
+    /// Count of all descendant nodes.
+    fn count(&self) -> usize {
+        self.children.values().map(|v| 1 + v.count()).sum()
     }
 
     /// Get node value full key name
     ///
     /// `node.get("abc_def")` => `node.get("abc").get("def")`
-    pub(crate) fn get(&self, k: &str) -> Option<&Node> {
+    pub fn get(&self, k: &str) -> Option<&Node> {
         match k.split_once('_') {
-            None => self.1.get(k),
-            Some((k, remain)) => match self.1.get(k) {
+            None => self.children.get(k),
+            Some((k, remain)) => match self.children.get(k) {
                 None => None,
                 Some(node) => node.get(remain),
             },
         }
     }
 
+    /// Take the node with full key name out of the tree.
+    ///
+    /// `node.take("abc_def")` => `node.get("abc").take("def")`
+    pub(crate) fn take(&mut self, k: &str) -> Option<Node> {
+        match k.split_once('_') {
+            None => self.children.remove(k),
+            Some((k, remain)) => self.children.get_mut(k)?.take(remain),
+        }
+    }
+
+    /// Get or create the node with full key name.
+    ///
+    /// `node.entry("abc_def")` => `node.entry("abc").entry("def")`
+    fn entry(&mut self, k: &str) -> &mut Node {
+        match k.split_once('_') {
+            None => self.children.entry(k.to_string()).or_default(),
+            Some((k, remain)) => self
+                .children
+                .entry(k.to_string())
+                .or_default()
+                .entry(remain),
+        }
+    }
+
     /// Push into node with full key name.
     ///
     /// `node.push("abc_def", v)` => `node.push("abc", "").push("def", v)`
-    pub(crate) fn push(&mut self, k: &str, v: &str) {
-        match k.split_once('_') {
+    pub(crate) fn push(&mut self, k: &str, v: impl Into<String>) {
+        let node = self.entry(k);
+        node.value = v.into();
+        node.raw = None;
+        node.present = true;
+    }
+
+    /// Push an `OsStr` value into node with full key name.
+    ///
+    /// The value is converted lossily, and the raw bytes are kept if the
+    /// conversion is not lossless.
+    pub(crate) fn push_os(&mut self, k: &str, v: &OsStr) {
+        let node = self.entry(k);
+        node.present = true;
+        match v.to_str() {
+            Some(v) => {
+                node.value = v.to_string();
+                node.raw = None;
+            }
             None => {
-                self.1
-                    .entry(k.to_string())
-                    .or_insert_with(|| Node::new(String::default()))
-                    .0 = v.to_string();
+                node.value = v.to_string_lossy().into_owned();
+                node.raw = Some(v.as_encoded_bytes().to_vec());
             }
-            Some((k, remain)) => match self.1.get_mut(k) {
-                None => {
-                    let mut node = Self::new(String::default());
-                    node.push(remain, v);
-                    self.1.insert(k.to_string(), node);
-                }
-                Some(node) => {
-                    node.push(remain, v);
-                }
-            },
-        };
+        }
     }
 
-    /// Construct full tree from an iterator.
-    pub(crate) fn from_iter<Iter, S>(iter: Iter) -> Self
+    /// Construct full tree from an iterator of `OsStr` pairs.
+    ///
+    /// Keys are converted into `String` lossily, values keep their raw bytes
+    /// if they are not valid UTF-8.
+    pub(crate) fn from_iter_os<Iter, K, V>(iter: Iter) -> Self
     where
-        S: AsRef<str>,
-        Iter: IntoIterator<Item = (S, S)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+        Iter: IntoIterator<Item = (K, V)>,
     {
-        let mut root = Node::new(String::default());
+        let mut root = Node::default();
 
         let vars = iter
             .into_iter()
-            .map(|(k, v)| (k.as_ref().to_lowercase(), v))
-            .filter(|(_, v)| !v.as_ref().is_empty());
+            .map(|(k, v)| (k.as_ref().to_string_lossy().to_lowercase(), v));
 
         for (k, v) in vars {
-            root.push(&k, v.as_ref())
+            root.push_os(&k, v.as_ref())
         }
 
         root
     }
 
     /// Construct full tree from an iterator with prefix.
-    pub(crate) fn from_iter_with_prefix<Iter, S>(iter: Iter, prefix: &str) -> Self
+    ///
+    /// `sep` is the boundary between prefix and the rest of key. The prefix
+    /// is matched case-insensitively.
+    pub(crate) fn from_iter_with_prefix<Iter, S>(iter: Iter, prefix: &str, sep: &str) -> Self
     where
         S: AsRef<str>,
         Iter: IntoIterator<Item = (S, S)>,
     {
-        let prefix = format!("{}_", prefix);
-        let mut root = Node::new(String::default());
+        let prefix = format!("{prefix}{sep}");
+        let mut root = Node::default();
 
         let vars = iter.into_iter().filter_map(|(k, v)| {
-            if v.as_ref().is_empty() {
-                None
-            } else {
-                k.as_ref()
-                    .strip_prefix(&prefix)
-                    .map(|k| (k.to_lowercase(), v))
-            }
+            strip_prefix_ignore_case(k.as_ref(), &prefix).map(|k| (k.to_lowercase(), v))
         });
 
         for (k, v) in vars {
@@ -161,14 +317,300 @@ impl Node {
         root
     }
 
+    /// Construct full tree from a map, with an optional prefix.
+    ///
+    /// Values are moved into the tree without copying. Uppercase keys win
+    /// over others differing only by case, since maps have no order.
+    pub(crate) fn from_map(map: HashMap<String, String>, prefix: Option<&str>, sep: &str) -> Self {
+        let prefix = prefix.map(|v| format!("{v}{sep}"));
+        let mut root = Node::default();
+
+        let mut vars: Vec<_> = map.into_iter().collect();
+        uppercase_last(&mut vars);
+        for (k, v) in vars {
+            let k = match &prefix {
+                None => k.as_str(),
+                Some(prefix) => match strip_prefix_ignore_case(&k, prefix) {
+                    Some(k) => k,
+                    None => continue,
+                },
+            };
+            root.push(&k.to_lowercase(), v)
+        }
+
+        root
+    }
+
+    /// Construct full tree from an iterator, with keys rewritten by `f`.
+    ///
+    /// Keys are dropped if `f` returns `None`.
+    pub(crate) fn from_iter_with_key_map<Iter, S, F>(iter: Iter, f: F) -> Self
+    where
+        S: AsRef<str>,
+        Iter: IntoIterator<Item = (S, S)>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut root = Node::default();
+
+        let vars = iter
+            .into_iter()
+            .filter_map(|(k, v)| f(k.as_ref()).map(|k| (k.to_lowercase(), v)));
+
+        for (k, v) in vars {
+            root.push(&k, v.as_ref())
+        }
+
+        root
+    }
+
+    /// Construct full tree from an iterator with several prefixes.
+    ///
+    /// Trees of every prefix are merged, and earlier prefixes win.
+    pub(crate) fn from_iter_with_prefixes<Iter, S>(iter: Iter, prefixes: &[&str], sep: &str) -> Self
+    where
+        S: AsRef<str>,
+        Iter: IntoIterator<Item = (S, S)>,
+    {
+        let vars: Vec<_> = iter.into_iter().collect();
+        let mut root = Node::default();
+
+        for prefix in prefixes {
+            let vars = vars.iter().map(|(k, v)| (k.as_ref(), v.as_ref()));
+            root.merge(Node::from_iter_with_prefix(vars, prefix, sep))
+        }
+
+        root
+    }
+
+    /// Merge another tree into this one.
+    ///
+    /// Keys present in `self` are kept, other keys are taken from `other`,
+    /// so config can be layered like env over defaults.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_env::Node;
+    ///
+    /// let mut node: Node = [("PORT", "8080")].into_iter().collect();
+    /// node.merge([("HOST", "localhost"), ("PORT", "80")].into_iter().collect());
+    ///
+    /// assert_eq!(node.get("host").map(Node::value), Some("localhost"));
+    /// assert_eq!(node.get("port").map(Node::value), Some("8080"));
+    /// ```
+    pub fn merge(&mut self, other: Node) {
+        if !self.present && other.present {
+            self.value = other.value;
+            self.raw = other.raw;
+            self.present = true;
+        }
+
+        for (k, v) in other.children {
+            match self.children.get_mut(&k) {
+                Some(node) => node.merge(v),
+                None => {
+                    self.children.insert(k, v);
+                }
+            }
+        }
+    }
+
     /// Construct full tree from env.
-    pub(crate) fn from_env() -> Self {
-        Node::from_iter(env::vars())
+    ///
+    /// If keys differ only by case like `PATH` and `path`, the uppercase
+    /// one wins, since env vars have no defined order.
+    pub fn from_env() -> Self {
+        Node::from_iter(env_vars_filtered(|_| true))
     }
 
     /// Construct full tree from env with prefix.
-    pub(crate) fn from_env_with_prefix(prefix: &str) -> Self {
-        Node::from_iter_with_prefix(env::vars(), prefix)
+    pub(crate) fn from_env_with_prefix(prefix: &str, sep: &str) -> Self {
+        Node::from_iter_with_prefix(env_vars_with_prefixes(&[prefix], sep), prefix, sep)
+    }
+
+    /// Construct full tree from env vars whose key matches the predicate.
+    pub(crate) fn from_env_filtered(predicate: impl Fn(&str) -> bool) -> Self {
+        Node::from_iter(env_vars_filtered(predicate))
+    }
+
+    /// Construct full tree from env with several prefixes.
+    pub(crate) fn from_env_with_prefixes(prefixes: &[&str], sep: &str) -> Self {
+        Node::from_iter_with_prefixes(env_vars_with_prefixes(prefixes, sep), prefixes, sep)
+    }
+}
+
+/// Read env vars whose key starts with any of prefixes.
+fn env_vars_with_prefixes(prefixes: &[&str], sep: &str) -> Vec<(String, String)> {
+    let prefixes: Vec<_> = prefixes.iter().map(|v| format!("{v}{sep}")).collect();
+
+    env_vars_filtered(|k| {
+        prefixes
+            .iter()
+            .any(|v| strip_prefix_ignore_case(k, v).is_some())
+    })
+}
+
+/// Strip the prefix from key, ignoring ASCII case like keys themselves, so
+/// prefix `APP_` matches `app_port` and `App_Port` too.
+fn strip_prefix_ignore_case<'a>(k: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = k.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &k[prefix.len()..])
+}
+
+/// Read env vars whose key matches the predicate.
+///
+/// Other vars are skipped before they are converted into `String`, so they
+/// cost less and never panic even if they are not valid unicode. Matched
+/// vars are converted lossily.
+pub(crate) fn env_vars_filtered(predicate: impl Fn(&str) -> bool) -> Vec<(String, String)> {
+    let mut vars: Vec<_> = env::vars_os()
+        .filter(|(k, _)| predicate(&k.to_string_lossy()))
+        .map(|(k, v)| {
+            (
+                k.to_string_lossy().into_owned(),
+                v.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    uppercase_last(&mut vars);
+    vars
+}
+
+/// Check if a key has no lowercase letters, like `PATH`.
+pub(crate) fn is_uppercase(k: &str) -> bool {
+    !k.chars().any(char::is_lowercase)
+}
+
+/// Move uppercase keys like `PATH` after others like `path`, so that they
+/// win when keys collide after lowercasing. Order is kept otherwise.
+fn uppercase_last<S: AsRef<str>, V>(vars: &mut [(S, V)]) {
+    vars.sort_by_key(|(k, _)| is_uppercase(k.as_ref()));
+}
+
+/// Construct full tree from an iterator.
+impl<S: AsRef<str>> FromIterator<(S, S)> for Node {
+    fn from_iter<Iter: IntoIterator<Item = (S, S)>>(iter: Iter) -> Self {
+        let mut root = Node::default();
+
+        let vars = iter
+            .into_iter()
+            .map(|(k, v)| (k.as_ref().to_lowercase(), v));
+
+        for (k, v) in vars {
+            root.push(&k, v.as_ref())
+        }
+
+        root
+    }
+}
+
+/// Capture a raw tree, like a `#[serde(flatten)]` field that keeps unknown
+/// keys for later processing via [`from_node`](crate::from_node).
+///
+/// Sequences of leaves are joined by `,`, and other sequences are kept as
+/// indexed children like `a_0`. Since `Deserialize` can't see the options,
+/// values split by another [`list_delimiter`] are joined by `,` too, so read
+/// the captured node with the default delimiter.
+///
+/// [`list_delimiter`]: crate::EnvDeserializer::list_delimiter
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a tree of env values")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Node::new(v.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Node::new(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Node::new(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Node::default())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Node::default())
+    }
+
+    fn visit_some<D>(self, d: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Node::deserialize(d)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = Vec::new();
+        while let Some(v) = seq.next_element::<Node>()? {
+            elements.push(v);
+        }
+
+        if elements.iter().all(|v| !v.has_children()) {
+            let values: Vec<_> = elements.iter().map(|v| v.value()).collect();
+            return Ok(Node::new(values.join(",")));
+        }
+
+        let mut node = Node::default();
+        for (idx, v) in elements.into_iter().enumerate() {
+            node.children.insert(idx.to_string(), v);
+        }
+        Ok(node)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut node = Node::default();
+        // Flat keys like `a_b` and nested keys like `a` are merged into
+        // the same tree.
+        while let Some((k, v)) = map.next_entry::<String, Node>()? {
+            node.entry(&k.to_lowercase()).merge(v);
+        }
+        Ok(node)
     }
 }
 
@@ -176,9 +618,19 @@ impl Node {
 mod tests {
     use super::*;
 
+    /// Build a node from its value and children.
+    fn node<const N: usize>(v: &str, children: [(&str, Node); N]) -> Node {
+        Node {
+            present: !v.is_empty(),
+            value: v.to_string(),
+            raw: None,
+            children: children.map(|(k, v)| (k.to_string(), v)).into(),
+        }
+    }
+
     #[test]
     fn test_get() {
-        let mut root = Node::new("");
+        let mut root = Node::default();
 
         root.push("a_b_c_d", "Hello, World!");
         root.push("a_b_c_e", "Hello, Mars!");
@@ -189,19 +641,19 @@ mod tests {
         assert_eq!(root.get("a_b_f"), Some(&Node::new("Hello, Moon!")));
         assert_eq!(
             root.get("a_b_c"),
-            Some(&Node(
-                "".to_string(),
-                BTreeMap::from([
-                    ("d".to_string(), Node::new("Hello, World!")),
-                    ("e".to_string(), Node::new("Hello, Mars!"))
-                ])
+            Some(&node(
+                "",
+                [
+                    ("d", Node::new("Hello, World!")),
+                    ("e", Node::new("Hello, Mars!"))
+                ]
             ))
         );
     }
 
     #[test]
     fn test_push() {
-        let mut root = Node::new("");
+        let mut root = Node::default();
 
         root.push("a_b_c_d", "Hello, World!");
         root.push("a_b_c_e", "Hello, Mars!");
@@ -210,60 +662,292 @@ mod tests {
 
         assert_eq!(
             root,
-            Node(
-                "".to_string(),
-                BTreeMap::from([(
-                    "a".to_string(),
-                    Node(
-                        "Hello, Earth!".to_string(),
-                        BTreeMap::from([(
-                            "b".to_string(),
-                            Node(
-                                "".to_string(),
-                                BTreeMap::from([
+            node(
+                "",
+                [(
+                    "a",
+                    node(
+                        "Hello, Earth!",
+                        [(
+                            "b",
+                            node(
+                                "",
+                                [
                                     (
-                                        "c".to_string(),
-                                        Node(
-                                            "".to_string(),
-                                            BTreeMap::from([
-                                                ("d".to_string(), Node::new("Hello, World!")),
-                                                ("e".to_string(), Node::new("Hello, Mars!"))
-                                            ])
+                                        "c",
+                                        node(
+                                            "",
+                                            [
+                                                ("d", Node::new("Hello, World!")),
+                                                ("e", Node::new("Hello, Mars!"))
+                                            ]
                                         )
                                     ),
-                                    ("f".to_string(), Node::new("Hello, Moon!"))
-                                ])
+                                    ("f", Node::new("Hello, Moon!"))
+                                ]
                             )
-                        )])
+                        )]
                     )
-                )])
+                )]
             )
         )
     }
 
     #[test]
     fn test_flatten() {
-        let mut root = Node::new("");
+        let mut root = Node::default();
 
         root.push("a", "Hello, World!");
         root.push("a_b_c_d", "Hello, World!");
         root.push("a_b_c_e", "Hello, Mars!");
         root.push("a_b_f", "Hello, Moon!");
 
-        let mut expected = HashSet::<String>::new();
-        expected.insert("a".to_owned());
-        expected.insert("a_b".to_owned());
-        expected.insert("a_b_c".to_owned());
-        expected.insert("a_b_c_d".to_owned());
-        expected.insert("a_b_c_e".to_owned());
-        expected.insert("a_b_f".to_owned());
+        let expected = ["a", "a_b", "a_b_c", "a_b_c_d", "a_b_c_e", "a_b_f"];
+        assert_eq!(root.flatten(), expected);
 
-        assert_eq!(root.flatten(""), expected);
+        // Buffers can be reused.
+        let (mut buf, mut keys) = (String::new(), Vec::new());
+        root.flatten_into(&mut buf, &mut keys);
+        assert!(buf.is_empty());
+        keys.clear();
+        root.flatten_into(&mut buf, &mut keys);
+        assert_eq!(keys, expected);
+
+        let mut root = Node::default();
+        for k in ["a_10", "a_2", "a_x", "a_1", "a_11", "a_0"] {
+            root.push(k, "v");
+        }
+        assert_eq!(
+            root.flatten(),
+            ["a", "a_0", "a_1", "a_2", "a_10", "a_11", "a_x"]
+        );
     }
+
+    #[test]
+    fn test_indexed_children() {
+        let mut root = Node::default();
+
+        root.push("a_10", "ten");
+        root.push("a_2", "two");
+        root.push("a_0", "zero");
+        root.push("b_0", "zero");
+        root.push("b_x", "x");
+
+        assert!(root.get("a").unwrap().has_indexed_children());
+        assert!(!root.get("b").unwrap().has_indexed_children());
+        assert_eq!(
+            root.get("a").unwrap().clone().into_indexed_children(),
+            vec![
+                (0, Node::new("zero")),
+                (2, Node::new("two")),
+                (10, Node::new("ten"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presence() {
+        let root = Node::from_iter([("A_B", ""), ("A_C", "Hello"), ("D", "")]);
+
+        let a = root.get("a").expect("must exist");
+        assert!(!a.is_absent());
+        assert!(!a.is_present_empty());
+
+        let b = root.get("a_b").expect("must exist");
+        assert!(!b.is_absent());
+        assert!(b.is_present_empty());
+
+        let c = root.get("a_c").expect("must exist");
+        assert!(!c.is_absent());
+        assert!(!c.is_present_empty());
+
+        assert!(root.get("d").expect("must exist").is_present_empty());
+        assert!(root.get("e").is_none());
+        assert!(Node::default().is_absent());
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let root = Node::from_iter([("A_B", "1"), ("C_D", ""), ("E", "")]);
+
+        // Children without own value.
+        let a = root.get("a").expect("must exist");
+        assert_eq!(a.value(), "");
+        assert!(!a.is_empty());
+        assert!(!a.is_empty_tree());
+
+        // Children with only empty values.
+        let c = root.get("c").expect("must exist");
+        assert!(!c.is_empty());
+        assert!(c.is_empty_tree());
+
+        assert!(root.get("e").expect("must exist").is_empty());
+        assert!(Node::default().is_empty());
+    }
+
     #[test]
     fn test_prefix() {
         std::env::set_var("TEST_ENV_VAR", "Hello, World!");
-        let root = Node::from_env_with_prefix("TEST_ENV");
+        let root = Node::from_env_with_prefix("TEST_ENV", "_");
         assert_eq!(root.get("var"), Some(&Node::new("Hello, World!")));
     }
+
+    #[test]
+    fn test_prefix_separator() {
+        let root = Node::from_iter_with_prefix(
+            [
+                ("APP__HOME", "/test"),
+                ("APP__DB_URL", "localhost"),
+                ("APP_OTHER", "other"),
+            ],
+            "APP",
+            "__",
+        );
+
+        assert_eq!(
+            root,
+            node(
+                "",
+                [
+                    ("db", node("", [("url", Node::new("localhost"))])),
+                    ("home", Node::new("/test")),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_prefixes() {
+        let root = Node::from_iter_with_prefixes(
+            [
+                ("NEW_A", "new"),
+                ("OLD_A", "old"),
+                ("OLD_B", "old"),
+                ("NEW_C_D", "new"),
+                ("OLD_C_E", "old"),
+                ("OTHER_F", "other"),
+            ],
+            &["NEW", "OLD"],
+            "_",
+        );
+
+        assert_eq!(
+            root,
+            node(
+                "",
+                [
+                    ("a", Node::new("new")),
+                    ("b", Node::new("old")),
+                    (
+                        "c",
+                        node("", [("d", Node::new("new")), ("e", Node::new("old"))])
+                    ),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_from_iter_os() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let root = Node::from_iter_os([
+            (OsString::from("A_B"), OsString::from("Hello")),
+            (
+                OsString::from("A_C"),
+                OsString::from_vec(vec![0x66, 0x6f, 0x80]),
+            ),
+        ]);
+
+        assert_eq!(root.get("a_b"), Some(&Node::new("Hello")));
+
+        let c = root.get("a_c").expect("must exist");
+        assert_eq!(c.value(), "fo\u{FFFD}");
+        assert_eq!(c.value_bytes(), &[0x66, 0x6f, 0x80]);
+
+        // Raw bytes of an overwritten value are dropped.
+        let mut root = root;
+        root.push("a_c", "foo");
+        assert_eq!(root.get("a_c").expect("must exist").value_bytes(), b"foo");
+    }
+
+    #[test]
+    fn test_from_env_with_prefix_skips_others() {
+        let mut vars: Vec<_> = (0..200)
+            .map(|i| (format!("SERDE_ENV_UNRELATED_{i}"), Some(i.to_string())))
+            .collect();
+        vars.push(("SERDE_ENV_SKIP_NAME".to_string(), Some("app".to_string())));
+        vars.push(("SERDE_ENV_SKIP_DB_URL".to_string(), Some("x".to_string())));
+        // Shares the prefix but not the separator.
+        vars.push((
+            "SERDE_ENV_SKIPPED_NAME".to_string(),
+            Some("other".to_string()),
+        ));
+
+        temp_env::with_vars(vars, || {
+            let node = Node::from_env_with_prefix("SERDE_ENV_SKIP", "_");
+            assert_eq!(node.childrens(), ["db", "name"]);
+            assert_eq!(node.get("db_url").unwrap().value(), "x");
+            assert!(node.get("ped_name").is_none());
+            assert!(node.get("unrelated_0").is_none());
+
+            let node = Node::from_env_with_prefixes(&["SERDE_ENV_NONE", "SERDE_ENV_SKIP"], "_");
+            assert_eq!(node.get("name").unwrap().value(), "app");
+            assert!(node.get("ped_name").is_none());
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_env_with_prefix_non_unicode_others() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = OsStr::from_bytes(b"\xff\xfe");
+        temp_env::with_vars(
+            [
+                (OsStr::new("SERDE_ENV_INVALID"), Some(invalid)),
+                (OsStr::new("SERDE_ENV_VALID_NAME"), Some(OsStr::new("app"))),
+            ],
+            || {
+                let node = Node::from_env_with_prefix("SERDE_ENV_VALID", "_");
+                assert_eq!(node.get("name").unwrap().value(), "app");
+            },
+        )
+    }
+
+    #[test]
+    fn test_prefix_ignore_case() {
+        let root = Node::from_iter_with_prefix(
+            [
+                ("test_env_home", "/lower"),
+                ("Test_Env_Path", "/mixed"),
+                ("TEST_ENVIRONMENT", "other"),
+            ],
+            "TEST_ENV",
+            "_",
+        );
+        assert_eq!(root.get("home"), Some(&Node::new("/lower")));
+        assert_eq!(root.get("path"), Some(&Node::new("/mixed")));
+        assert_eq!(root.childrens().len(), 2);
+
+        temp_env::with_var("Serde_Env_Case_Name", Some("app"), || {
+            let root = Node::from_env_with_prefix("SERDE_ENV_CASE", "_");
+            assert_eq!(root.get("name"), Some(&Node::new("app")));
+        });
+    }
+
+    #[test]
+    fn test_deserialize_int128() {
+        use serde::de::value::{Error, I128Deserializer, U128Deserializer};
+
+        let v = u64::MAX as u128 + 1;
+        let node = Node::deserialize(U128Deserializer::<Error>::new(v)).expect("must success");
+        assert_eq!(node, Node::new("18446744073709551616"));
+
+        let v = i64::MIN as i128 - 1;
+        let node = Node::deserialize(I128Deserializer::<Error>::new(v)).expect("must success");
+        assert_eq!(node, Node::new("-9223372036854775809"));
+    }
 }
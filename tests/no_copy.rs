@@ -0,0 +1,80 @@
+//! Large values should be moved into fields without being copied.
+//!
+//! This lives in its own test binary since it needs a global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+const LARGE: usize = 4 << 20;
+
+/// Counts allocations of at least [`LARGE`] bytes made by current thread.
+struct Counter;
+
+thread_local! {
+    static LARGE_ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= LARGE {
+            LARGE_ALLOCS.with(|v| v.set(v.get() + 1));
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size >= LARGE {
+            LARGE_ALLOCS.with(|v| v.set(v.get() + 1));
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+fn large_allocs() -> usize {
+    LARGE_ALLOCS.with(|v| v.get())
+}
+
+#[derive(Deserialize)]
+struct Inner {
+    blob: String,
+}
+
+#[derive(Deserialize)]
+struct Test {
+    blob: String,
+    maybe: Option<String>,
+    inner: Inner,
+}
+
+#[test]
+fn test_large_values_are_moved() {
+    let map: HashMap<String, String> = ["BLOB", "MAYBE", "INNER_BLOB"]
+        .into_iter()
+        .map(|k| (k.to_string(), "x".repeat(LARGE)))
+        .collect();
+    let ptrs: Vec<_> = ["BLOB", "MAYBE", "INNER_BLOB"]
+        .iter()
+        .map(|k| map[*k].as_ptr())
+        .collect();
+
+    let before = large_allocs();
+    let t: Test = serde_env::from_map(map).expect("must success");
+    assert_eq!(large_allocs(), before, "large values must not be copied");
+
+    let maybe = t.maybe.expect("maybe must be set");
+    assert_eq!(t.blob.len(), LARGE);
+    assert_eq!(
+        [t.blob.as_ptr(), maybe.as_ptr(), t.inner.blob.as_ptr()],
+        ptrs[..]
+    );
+}